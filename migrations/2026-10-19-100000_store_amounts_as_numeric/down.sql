@@ -0,0 +1,21 @@
+ALTER TABLE daos
+  ALTER COLUMN proposal_count TYPE BIGINT,
+  ALTER COLUMN pass_proposal_count TYPE BIGINT,
+  ALTER COLUMN vote_count TYPE BIGINT,
+  ALTER COLUMN passed_votes_proportion TYPE BIGINT,
+  ALTER COLUMN passed_tokens_proportion TYPE BIGINT;
+
+ALTER TABLE proposals
+  ALTER COLUMN adopt TYPE BIGINT,
+  ALTER COLUMN reject TYPE BIGINT;
+
+ALTER TABLE votes ALTER COLUMN amount TYPE BIGINT;
+
+ALTER TABLE stake_amounts ALTER COLUMN amount TYPE BIGINT;
+
+ALTER TABLE balances ALTER COLUMN amount TYPE BIGINT;
+
+ALTER TABLE token_infos
+  ALTER COLUMN supply TYPE BIGINT,
+  ALTER COLUMN max_mint_amount TYPE BIGINT,
+  ALTER COLUMN minted_amount TYPE BIGINT;
@@ -0,0 +1,22 @@
+-- u64 values of the program don't fit in BIGINT above i64::MAX.
+ALTER TABLE token_infos
+  ALTER COLUMN supply TYPE NUMERIC(20,0),
+  ALTER COLUMN max_mint_amount TYPE NUMERIC(20,0),
+  ALTER COLUMN minted_amount TYPE NUMERIC(20,0);
+
+ALTER TABLE balances ALTER COLUMN amount TYPE NUMERIC(20,0);
+
+ALTER TABLE stake_amounts ALTER COLUMN amount TYPE NUMERIC(20,0);
+
+ALTER TABLE votes ALTER COLUMN amount TYPE NUMERIC(20,0);
+
+ALTER TABLE proposals
+  ALTER COLUMN adopt TYPE NUMERIC(20,0),
+  ALTER COLUMN reject TYPE NUMERIC(20,0);
+
+ALTER TABLE daos
+  ALTER COLUMN proposal_count TYPE NUMERIC(20,0),
+  ALTER COLUMN pass_proposal_count TYPE NUMERIC(20,0),
+  ALTER COLUMN vote_count TYPE NUMERIC(20,0),
+  ALTER COLUMN passed_votes_proportion TYPE NUMERIC(20,0),
+  ALTER COLUMN passed_tokens_proportion TYPE NUMERIC(20,0);
@@ -0,0 +1,9 @@
+DROP TABLE record_outputs;
+DROP TABLE record_inputs;
+
+DROP INDEX idx_record_outputs_gin;
+DROP INDEX idx_record_inputs_gin;
+
+ALTER TABLE record
+  ALTER COLUMN inputs TYPE TEXT USING inputs::text,
+  ALTER COLUMN outputs TYPE TEXT USING outputs::text;
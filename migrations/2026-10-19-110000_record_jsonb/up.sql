@@ -0,0 +1,41 @@
+ALTER TABLE record
+  ALTER COLUMN inputs TYPE JSONB USING inputs::jsonb,
+  ALTER COLUMN outputs TYPE JSONB USING outputs::jsonb;
+
+-- Containment searches such as `inputs @> '[{"type": "record"}]'`.
+CREATE INDEX idx_record_inputs_gin ON record USING GIN (inputs jsonb_path_ops);
+CREATE INDEX idx_record_outputs_gin ON record USING GIN (outputs jsonb_path_ops);
+
+CREATE TABLE record_inputs (
+  transition_id TEXT NOT NULL,
+  position BIGINT NOT NULL,
+  type TEXT NOT NULL,
+  id TEXT NOT NULL,
+  value TEXT NOT NULL,
+  tag TEXT NOT NULL,
+  PRIMARY KEY (transition_id, position)
+);
+
+CREATE TABLE record_outputs (
+  transition_id TEXT NOT NULL,
+  position BIGINT NOT NULL,
+  type TEXT NOT NULL,
+  id TEXT NOT NULL,
+  checksum TEXT NOT NULL,
+  value TEXT NOT NULL,
+  PRIMARY KEY (transition_id, position)
+);
+
+INSERT INTO record_inputs (transition_id, position, type, id, value, tag)
+SELECT r.transition_id, i.position - 1, i.elem->>'type', i.elem->>'id', i.elem->>'value', i.elem->>'tag'
+FROM record r, jsonb_array_elements(r.inputs) WITH ORDINALITY AS i(elem, position);
+
+INSERT INTO record_outputs (transition_id, position, type, id, checksum, value)
+SELECT r.transition_id, o.position - 1, o.elem->>'type', o.elem->>'id', o.elem->>'checksum', o.elem->>'value'
+FROM record r, jsonb_array_elements(r.outputs) WITH ORDINALITY AS o(elem, position);
+
+CREATE INDEX idx_record_inputs_id ON record_inputs (id);
+CREATE INDEX idx_record_outputs_id ON record_outputs (id);
+-- Record ciphertexts can exceed the btree row size limit.
+CREATE INDEX idx_record_inputs_value ON record_inputs USING HASH (value);
+CREATE INDEX idx_record_outputs_value ON record_outputs USING HASH (value);
@@ -0,0 +1,17 @@
+ALTER TABLE stake_amounts DROP CONSTRAINT fk_stake_amounts_token_info_id;
+ALTER TABLE balances DROP CONSTRAINT fk_balances_token_info_id;
+ALTER TABLE votes DROP CONSTRAINT fk_votes_proposal_id;
+ALTER TABLE proposals DROP CONSTRAINT fk_proposals_dao_id;
+ALTER TABLE record_outputs DROP CONSTRAINT fk_record_outputs_transition_id;
+ALTER TABLE record_inputs DROP CONSTRAINT fk_record_inputs_transition_id;
+
+DROP INDEX idx_record_program_function;
+DROP INDEX idx_stake_amounts_token_info_id;
+DROP INDEX idx_stake_amounts_owner;
+DROP INDEX idx_balances_token_info_id;
+DROP INDEX idx_balances_owner;
+DROP INDEX idx_votes_voter;
+DROP INDEX idx_votes_proposal_id;
+DROP INDEX idx_proposals_dao_id;
+DROP INDEX idx_daos_token_info_id;
+DROP INDEX idx_token_infos_dao_id;
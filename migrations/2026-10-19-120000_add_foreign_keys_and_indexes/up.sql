@@ -0,0 +1,36 @@
+CREATE INDEX idx_token_infos_dao_id ON token_infos (dao_id);
+CREATE INDEX idx_daos_token_info_id ON daos (token_info_id);
+CREATE INDEX idx_proposals_dao_id ON proposals (dao_id);
+CREATE INDEX idx_votes_proposal_id ON votes (proposal_id);
+CREATE INDEX idx_votes_voter ON votes (voter);
+CREATE INDEX idx_balances_owner ON balances (owner);
+CREATE INDEX idx_balances_token_info_id ON balances (token_info_id);
+CREATE INDEX idx_stake_amounts_owner ON stake_amounts (owner);
+CREATE INDEX idx_stake_amounts_token_info_id ON stake_amounts (token_info_id);
+CREATE INDEX idx_record_program_function ON record (program, function);
+
+-- Side tables are written together with their record.
+ALTER TABLE record_inputs
+  ADD CONSTRAINT fk_record_inputs_transition_id
+  FOREIGN KEY (transition_id) REFERENCES record (transition_id) ON DELETE CASCADE;
+ALTER TABLE record_outputs
+  ADD CONSTRAINT fk_record_outputs_transition_id
+  FOREIGN KEY (transition_id) REFERENCES record (transition_id) ON DELETE CASCADE;
+
+-- The program only creates proposals of existing DAOs, votes on existing
+-- proposals and balances/stakes of existing tokens. NOT VALID keeps rows
+-- synced from a start block after their parent was created; new rows are
+-- still checked. `daos` and `token_infos` reference each other and are
+-- inserted in either order, so they are left unconstrained.
+ALTER TABLE proposals
+  ADD CONSTRAINT fk_proposals_dao_id
+  FOREIGN KEY (dao_id) REFERENCES daos (id) NOT VALID;
+ALTER TABLE votes
+  ADD CONSTRAINT fk_votes_proposal_id
+  FOREIGN KEY (proposal_id) REFERENCES proposals (id) NOT VALID;
+ALTER TABLE balances
+  ADD CONSTRAINT fk_balances_token_info_id
+  FOREIGN KEY (token_info_id) REFERENCES token_infos (id) NOT VALID;
+ALTER TABLE stake_amounts
+  ADD CONSTRAINT fk_stake_amounts_token_info_id
+  FOREIGN KEY (token_info_id) REFERENCES token_infos (id) NOT VALID;
@@ -0,0 +1,34 @@
+ALTER TABLE daos
+  DROP COLUMN updated_at_height,
+  DROP COLUMN updated_by_transition_id,
+  DROP COLUMN updated_at_timestamp;
+
+ALTER TABLE proposals
+  DROP COLUMN updated_at_height,
+  DROP COLUMN updated_by_transition_id,
+  DROP COLUMN updated_at_timestamp;
+
+ALTER TABLE votes
+  DROP COLUMN updated_at_height,
+  DROP COLUMN updated_by_transition_id,
+  DROP COLUMN updated_at_timestamp;
+
+ALTER TABLE token_infos
+  DROP COLUMN updated_at_height,
+  DROP COLUMN updated_by_transition_id,
+  DROP COLUMN updated_at_timestamp;
+
+ALTER TABLE balances
+  DROP COLUMN updated_at_height,
+  DROP COLUMN updated_by_transition_id,
+  DROP COLUMN updated_at_timestamp;
+
+ALTER TABLE stake_amounts
+  DROP COLUMN updated_at_height,
+  DROP COLUMN updated_by_transition_id,
+  DROP COLUMN updated_at_timestamp;
+
+ALTER TABLE profiles
+  DROP COLUMN updated_at_height,
+  DROP COLUMN updated_by_transition_id,
+  DROP COLUMN updated_at_timestamp;
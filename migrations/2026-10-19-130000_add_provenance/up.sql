@@ -0,0 +1,36 @@
+-- Block and transition that last wrote each row derived from the program.
+-- Rows indexed before this migration, or written through the API, have none.
+ALTER TABLE daos
+  ADD COLUMN updated_at_height BIGINT,
+  ADD COLUMN updated_by_transition_id TEXT,
+  ADD COLUMN updated_at_timestamp BIGINT;
+
+ALTER TABLE proposals
+  ADD COLUMN updated_at_height BIGINT,
+  ADD COLUMN updated_by_transition_id TEXT,
+  ADD COLUMN updated_at_timestamp BIGINT;
+
+ALTER TABLE votes
+  ADD COLUMN updated_at_height BIGINT,
+  ADD COLUMN updated_by_transition_id TEXT,
+  ADD COLUMN updated_at_timestamp BIGINT;
+
+ALTER TABLE token_infos
+  ADD COLUMN updated_at_height BIGINT,
+  ADD COLUMN updated_by_transition_id TEXT,
+  ADD COLUMN updated_at_timestamp BIGINT;
+
+ALTER TABLE balances
+  ADD COLUMN updated_at_height BIGINT,
+  ADD COLUMN updated_by_transition_id TEXT,
+  ADD COLUMN updated_at_timestamp BIGINT;
+
+ALTER TABLE stake_amounts
+  ADD COLUMN updated_at_height BIGINT,
+  ADD COLUMN updated_by_transition_id TEXT,
+  ADD COLUMN updated_at_timestamp BIGINT;
+
+ALTER TABLE profiles
+  ADD COLUMN updated_at_height BIGINT,
+  ADD COLUMN updated_by_transition_id TEXT,
+  ADD COLUMN updated_at_timestamp BIGINT;
@@ -0,0 +1,29 @@
+CREATE TABLE blocks (
+  height BIGINT PRIMARY KEY,
+  block_hash TEXT NOT NULL,
+  -- Only known from the transitions of the block, NULL for blocks without any.
+  previous_hash TEXT,
+  timestamp BIGINT NOT NULL,
+  transaction_count BIGINT NOT NULL,
+  transition_count BIGINT NOT NULL
+);
+
+CREATE INDEX idx_blocks_block_hash ON blocks (block_hash);
+
+CREATE TABLE transactions (
+  transaction_id TEXT PRIMARY KEY,
+  height BIGINT NOT NULL REFERENCES blocks (height) ON DELETE CASCADE,
+  transition_count BIGINT NOT NULL
+);
+
+CREATE INDEX idx_transactions_height ON transactions (height);
+
+INSERT INTO blocks (height, block_hash, previous_hash, timestamp, transaction_count, transition_count)
+SELECT height, MIN(block_hash), MIN(previous_hash), MIN(timestamp), COUNT(DISTINCT transaction_id), COUNT(*)
+FROM record
+GROUP BY height;
+
+INSERT INTO transactions (transaction_id, height, transition_count)
+SELECT transaction_id, MIN(height), COUNT(*)
+FROM record
+GROUP BY transaction_id;
@@ -0,0 +1,8 @@
+-- Last block written by each sink, updated in the same transaction as the
+-- block's rows for the database sink.
+CREATE TABLE cursors (
+  name TEXT PRIMARY KEY,
+  cursor TEXT NOT NULL,
+  height BIGINT NOT NULL,
+  block_hash TEXT NOT NULL
+);
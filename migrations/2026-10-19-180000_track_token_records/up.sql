@@ -0,0 +1,27 @@
+-- `token` was keyed by owner and never written. Token records are tracked
+-- one row per record instead, keyed by commitment. The tag of a record is
+-- only known to its owner, so it is NULL until it is computed with the
+-- owner's view key. Until then the record can't be matched with the input
+-- that spends it.
+DROP TABLE token;
+
+CREATE TABLE token (
+  commitment TEXT PRIMARY KEY,
+  owner TEXT NOT NULL,
+  gates NUMERIC(20,0) NOT NULL,
+  token_info_id BIGINT NOT NULL,
+  amount NUMERIC(20,0) NOT NULL,
+  expires BIGINT NOT NULL,
+  staked_at BIGINT NOT NULL,
+  function TEXT NOT NULL,
+  created_at_height BIGINT NOT NULL,
+  created_by_transition_id TEXT NOT NULL,
+  created_at_timestamp BIGINT NOT NULL,
+  tag TEXT,
+  serial_number TEXT,
+  spent_at_height BIGINT,
+  spent_by_transition_id TEXT
+);
+
+CREATE INDEX idx_token_owner ON token (owner);
+CREATE INDEX idx_token_tag ON token (tag);
@@ -0,0 +1,3 @@
+DROP INDEX idx_record_inputs_tag;
+ALTER TABLE token DROP COLUMN private;
+DROP TABLE view_keys;
@@ -0,0 +1,19 @@
+-- View keys registered by their owner, so that `sync` can decrypt the records
+-- they own. `encrypted_view_key` is the AES-256-GCM nonce followed by the
+-- ciphertext, under `VIEW_KEY_ENCRYPTION_KEY`. `access_token_hash` is the
+-- SHA-256 of the token given back at registration, in hex. The records synced
+-- before the registration are decrypted by the next block, which sets
+-- `backfilled_at_height`.
+CREATE TABLE view_keys (
+  address TEXT PRIMARY KEY,
+  encrypted_view_key BYTEA NOT NULL,
+  access_token_hash TEXT NOT NULL,
+  registered_at BIGINT NOT NULL,
+  backfilled_at_height BIGINT
+);
+
+-- Decrypted records of registered accounts, hidden from public listings.
+ALTER TABLE token ADD COLUMN private BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Finds the input spending a record once its tag is known.
+CREATE INDEX idx_record_inputs_tag ON record_inputs (tag);
@@ -0,0 +1,12 @@
+ALTER TABLE proposals
+  ADD CONSTRAINT fk_proposals_dao_id
+  FOREIGN KEY (dao_id) REFERENCES daos (id) NOT VALID;
+ALTER TABLE votes
+  ADD CONSTRAINT fk_votes_proposal_id
+  FOREIGN KEY (proposal_id) REFERENCES proposals (id) NOT VALID;
+ALTER TABLE balances
+  ADD CONSTRAINT fk_balances_token_info_id
+  FOREIGN KEY (token_info_id) REFERENCES token_infos (id) NOT VALID;
+ALTER TABLE stake_amounts
+  ADD CONSTRAINT fk_stake_amounts_token_info_id
+  FOREIGN KEY (token_info_id) REFERENCES token_infos (id) NOT VALID;
@@ -0,0 +1,8 @@
+-- A sync from a start block inserts proposals, votes, balances and stakes
+-- whose DAO, proposal or token info was created before that block and is
+-- never indexed, which these constraints rejected. The indexes on the
+-- columns are kept.
+ALTER TABLE stake_amounts DROP CONSTRAINT IF EXISTS fk_stake_amounts_token_info_id;
+ALTER TABLE balances DROP CONSTRAINT IF EXISTS fk_balances_token_info_id;
+ALTER TABLE votes DROP CONSTRAINT IF EXISTS fk_votes_proposal_id;
+ALTER TABLE proposals DROP CONSTRAINT IF EXISTS fk_proposals_dao_id;
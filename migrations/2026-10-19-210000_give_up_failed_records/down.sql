@@ -0,0 +1 @@
+ALTER TABLE failed_records DROP COLUMN gave_up;
@@ -0,0 +1,4 @@
+-- Failed records aren't retried by `sync` any more once they failed
+-- RETRY_MAX_ATTEMPTS times, see program_handler.rs. `retry <transition_id>`
+-- still handles them.
+ALTER TABLE failed_records ADD COLUMN gave_up BOOLEAN NOT NULL DEFAULT FALSE;
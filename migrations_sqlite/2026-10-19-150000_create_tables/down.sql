@@ -0,0 +1,14 @@
+DROP TABLE profiles;
+DROP TABLE stake_amounts;
+DROP TABLE balances;
+DROP TABLE votes;
+DROP TABLE proposals;
+DROP TABLE daos;
+DROP TABLE token_infos;
+DROP TABLE extend_pledge_period;
+DROP TABLE auto_increment;
+DROP TABLE transactions;
+DROP TABLE blocks;
+DROP TABLE record_outputs;
+DROP TABLE record_inputs;
+DROP TABLE record;
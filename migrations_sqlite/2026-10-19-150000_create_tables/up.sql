@@ -0,0 +1,185 @@
+-- The schema of `migrations` for the embedded SQLite storage. Amounts are
+-- stored as decimal TEXT since INTEGER can't hold values above i64::MAX, and
+-- record inputs/outputs as JSON TEXT. Only the side tables and transactions
+-- reference their parent: SQLite has no NOT VALID constraints to accept rows
+-- synced from a start block after their parent was created.
+CREATE TABLE record (
+  transition_id TEXT PRIMARY KEY,
+  program TEXT NOT NULL,
+  function TEXT NOT NULL,
+  inputs TEXT NOT NULL,
+  outputs TEXT NOT NULL,
+  block_hash TEXT NOT NULL,
+  previous_hash TEXT NOT NULL,
+  transaction_id TEXT NOT NULL,
+  network BIGINT NOT NULL,
+  height BIGINT NOT NULL,
+  timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX idx_height ON record (height);
+CREATE INDEX idx_record_program_function ON record (program, function);
+
+CREATE TABLE record_inputs (
+  transition_id TEXT NOT NULL REFERENCES record (transition_id) ON DELETE CASCADE,
+  position BIGINT NOT NULL,
+  type TEXT NOT NULL,
+  id TEXT NOT NULL,
+  value TEXT NOT NULL,
+  tag TEXT NOT NULL,
+  PRIMARY KEY (transition_id, position)
+);
+
+CREATE INDEX idx_record_inputs_id ON record_inputs (id);
+CREATE INDEX idx_record_inputs_value ON record_inputs (value);
+
+CREATE TABLE record_outputs (
+  transition_id TEXT NOT NULL REFERENCES record (transition_id) ON DELETE CASCADE,
+  position BIGINT NOT NULL,
+  type TEXT NOT NULL,
+  id TEXT NOT NULL,
+  checksum TEXT NOT NULL,
+  value TEXT NOT NULL,
+  PRIMARY KEY (transition_id, position)
+);
+
+CREATE INDEX idx_record_outputs_id ON record_outputs (id);
+CREATE INDEX idx_record_outputs_value ON record_outputs (value);
+
+CREATE TABLE blocks (
+  height BIGINT PRIMARY KEY,
+  block_hash TEXT NOT NULL,
+  previous_hash TEXT,
+  timestamp BIGINT NOT NULL,
+  transaction_count BIGINT NOT NULL,
+  transition_count BIGINT NOT NULL
+);
+
+CREATE INDEX idx_blocks_block_hash ON blocks (block_hash);
+
+CREATE TABLE transactions (
+  transaction_id TEXT PRIMARY KEY,
+  height BIGINT NOT NULL REFERENCES blocks (height) ON DELETE CASCADE,
+  transition_count BIGINT NOT NULL
+);
+
+CREATE INDEX idx_transactions_height ON transactions (height);
+
+CREATE TABLE auto_increment (
+  key BIGINT PRIMARY KEY,
+  value BIGINT NOT NULL
+);
+
+CREATE TABLE extend_pledge_period (
+  key BIGINT PRIMARY KEY,
+  value BIGINT NOT NULL
+);
+
+CREATE TABLE token_infos (
+  id BIGINT PRIMARY KEY,
+  name TEXT NOT NULL,
+  symbol TEXT NOT NULL,
+  supply TEXT NOT NULL,
+  decimals BIGINT NOT NULL,
+  max_mint_amount TEXT NOT NULL,
+  minted_amount TEXT NOT NULL,
+  dao_id BIGINT NOT NULL,
+  only_creator_can_mint BOOLEAN NOT NULL,
+  updated_at_height BIGINT,
+  updated_by_transition_id TEXT,
+  updated_at_timestamp BIGINT
+);
+
+CREATE INDEX idx_token_infos_dao_id ON token_infos (dao_id);
+
+CREATE TABLE daos (
+  id BIGINT PRIMARY KEY,
+  name TEXT NOT NULL,
+  dao_type BIGINT NOT NULL,
+  creator TEXT NOT NULL,
+  token_info_id BIGINT NOT NULL,
+  icon TEXT NOT NULL,
+  description TEXT NOT NULL,
+  official_link TEXT NOT NULL,
+  proposal_count TEXT NOT NULL,
+  pass_proposal_count TEXT NOT NULL,
+  vote_count TEXT NOT NULL,
+  passed_votes_proportion TEXT NOT NULL,
+  passed_tokens_proportion TEXT NOT NULL,
+  updated_at_height BIGINT,
+  updated_by_transition_id TEXT,
+  updated_at_timestamp BIGINT
+);
+
+CREATE INDEX idx_daos_token_info_id ON daos (token_info_id);
+
+CREATE TABLE proposals (
+  id BIGINT PRIMARY KEY,
+  title TEXT NOT NULL,
+  proposer TEXT NOT NULL,
+  summary TEXT NOT NULL,
+  body TEXT NOT NULL,
+  dao_id BIGINT NOT NULL,
+  created BIGINT NOT NULL,
+  duration BIGINT NOT NULL,
+  type BIGINT NOT NULL,
+  adopt TEXT NOT NULL,
+  reject TEXT NOT NULL,
+  status BIGINT NOT NULL,
+  updated_at_height BIGINT,
+  updated_by_transition_id TEXT,
+  updated_at_timestamp BIGINT
+);
+
+CREATE INDEX idx_proposals_dao_id ON proposals (dao_id);
+
+CREATE TABLE votes (
+  key TEXT PRIMARY KEY,
+  voter TEXT NOT NULL,
+  proposal_id BIGINT NOT NULL,
+  is_agreed BOOLEAN NOT NULL,
+  time BIGINT NOT NULL,
+  amount TEXT NOT NULL,
+  updated_at_height BIGINT,
+  updated_by_transition_id TEXT,
+  updated_at_timestamp BIGINT
+);
+
+CREATE INDEX idx_votes_proposal_id ON votes (proposal_id);
+CREATE INDEX idx_votes_voter ON votes (voter);
+
+CREATE TABLE balances (
+  key TEXT PRIMARY KEY,
+  owner TEXT NOT NULL,
+  amount TEXT NOT NULL,
+  token_info_id BIGINT NOT NULL,
+  updated_at_height BIGINT,
+  updated_by_transition_id TEXT,
+  updated_at_timestamp BIGINT
+);
+
+CREATE INDEX idx_balances_owner ON balances (owner);
+CREATE INDEX idx_balances_token_info_id ON balances (token_info_id);
+
+CREATE TABLE stake_amounts (
+  key TEXT PRIMARY KEY,
+  owner TEXT NOT NULL,
+  amount TEXT NOT NULL,
+  token_info_id BIGINT NOT NULL,
+  updated_at_height BIGINT,
+  updated_by_transition_id TEXT,
+  updated_at_timestamp BIGINT
+);
+
+CREATE INDEX idx_stake_amounts_owner ON stake_amounts (owner);
+CREATE INDEX idx_stake_amounts_token_info_id ON stake_amounts (token_info_id);
+
+CREATE TABLE profiles (
+  address TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  avatar TEXT NOT NULL,
+  bio TEXT NOT NULL,
+  updated_at_height BIGINT,
+  updated_by_transition_id TEXT,
+  updated_at_timestamp BIGINT
+);
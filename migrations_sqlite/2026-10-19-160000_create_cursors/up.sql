@@ -0,0 +1,6 @@
+CREATE TABLE cursors (
+  name TEXT PRIMARY KEY,
+  cursor TEXT NOT NULL,
+  height BIGINT NOT NULL,
+  block_hash TEXT NOT NULL
+);
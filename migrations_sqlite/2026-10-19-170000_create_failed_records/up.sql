@@ -0,0 +1,14 @@
+CREATE TABLE failed_records (
+  transition_id TEXT PRIMARY KEY,
+  program TEXT NOT NULL,
+  function TEXT NOT NULL,
+  height BIGINT NOT NULL,
+  record BLOB NOT NULL,
+  error TEXT NOT NULL,
+  attempts BIGINT NOT NULL,
+  first_failed_at BIGINT NOT NULL,
+  last_failed_at BIGINT NOT NULL,
+  next_retry_at BIGINT NOT NULL
+);
+
+CREATE INDEX failed_records_next_retry_at_idx ON failed_records (next_retry_at);
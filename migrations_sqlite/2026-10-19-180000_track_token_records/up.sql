@@ -0,0 +1,20 @@
+CREATE TABLE token (
+  commitment TEXT PRIMARY KEY,
+  owner TEXT NOT NULL,
+  gates TEXT NOT NULL,
+  token_info_id BIGINT NOT NULL,
+  amount TEXT NOT NULL,
+  expires BIGINT NOT NULL,
+  staked_at BIGINT NOT NULL,
+  function TEXT NOT NULL,
+  created_at_height BIGINT NOT NULL,
+  created_by_transition_id TEXT NOT NULL,
+  created_at_timestamp BIGINT NOT NULL,
+  tag TEXT,
+  serial_number TEXT,
+  spent_at_height BIGINT,
+  spent_by_transition_id TEXT
+);
+
+CREATE INDEX idx_token_owner ON token (owner);
+CREATE INDEX idx_token_tag ON token (tag);
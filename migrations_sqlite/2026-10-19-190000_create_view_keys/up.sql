@@ -0,0 +1,11 @@
+CREATE TABLE view_keys (
+  address TEXT PRIMARY KEY,
+  encrypted_view_key BLOB NOT NULL,
+  access_token_hash TEXT NOT NULL,
+  registered_at BIGINT NOT NULL,
+  backfilled_at_height BIGINT
+);
+
+ALTER TABLE token ADD COLUMN private BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX idx_record_inputs_tag ON record_inputs (tag);
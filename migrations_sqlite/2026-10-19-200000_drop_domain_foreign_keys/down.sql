@@ -0,0 +1,2 @@
+-- Nothing to restore, see up.sql.
+SELECT 1;
@@ -0,0 +1,2 @@
+-- The SQLite schema never had the foreign keys dropped from Postgres.
+SELECT 1;
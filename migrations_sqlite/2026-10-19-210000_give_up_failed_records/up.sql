@@ -0,0 +1 @@
+ALTER TABLE failed_records ADD COLUMN gave_up BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,122 @@
+use anyhow::{anyhow, Error};
+use async_graphql::{InputValueError, InputValueResult, Scalar, ScalarType, Value};
+use bigdecimal::BigDecimal;
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow},
+    expression::AsExpression,
+    pg::{Pg, PgValue},
+    serialize::{self, Output, ToSql},
+    sql_types::Numeric,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+use utoipa::{
+    openapi::{ObjectBuilder, RefOr, Schema, SchemaType},
+    ToSchema,
+};
+
+/// A `u64` quantity of the program (token amounts and supplies, vote weights,
+/// counters). Stored as `NUMERIC(20,0)` since `BIGINT` can't hold values above
+/// `i64::MAX`, and serialized as a decimal string so JavaScript clients don't
+/// lose precision.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Numeric)]
+pub struct Amount(pub BigDecimal);
+
+impl From<u64> for Amount {
+    fn from(value: u64) -> Self {
+        Amount(BigDecimal::from(value))
+    }
+}
+
+impl From<i64> for Amount {
+    fn from(value: i64) -> Self {
+        Amount(BigDecimal::from(value))
+    }
+}
+
+impl FromStr for Amount {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = BigDecimal::from_str(s)?;
+        if !value.is_integer() || value < BigDecimal::default() {
+            return Err(anyhow!("{} is not an unsigned integer", s));
+        }
+        Ok(Amount(value.with_scale(0)))
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            String(String),
+            Number(u64),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::String(value) => value.parse().map_err(serde::de::Error::custom),
+            Repr::Number(value) => Ok(value.into()),
+        }
+    }
+}
+
+impl FromSql<Numeric, Pg> for Amount {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        Ok(Amount(<BigDecimal as FromSql<Numeric, Pg>>::from_sql(
+            bytes,
+        )?))
+    }
+}
+
+impl ToSql<Numeric, Pg> for Amount {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <BigDecimal as ToSql<Numeric, Pg>>::to_sql(&self.0, out)
+    }
+}
+
+#[Scalar(name = "Amount")]
+/// Unsigned 64-bit integer encoded as a decimal string.
+impl ScalarType for Amount {
+    fn parse(value: Value) -> InputValueResult<Self> {
+        match &value {
+            Value::String(s) => s.parse().map_err(InputValueError::custom),
+            Value::Number(n) => n
+                .as_u64()
+                .map(Amount::from)
+                .ok_or_else(|| InputValueError::expected_type(value.clone())),
+            _ => Err(InputValueError::expected_type(value)),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl<'s> ToSchema<'s> for Amount {
+    fn schema() -> (&'s str, RefOr<Schema>) {
+        (
+            "Amount",
+            ObjectBuilder::new()
+                .schema_type(SchemaType::String)
+                .description(Some("Unsigned 64-bit integer encoded as a decimal string"))
+                .example(Some("1000000".into()))
+                .into(),
+        )
+    }
+}
@@ -0,0 +1,277 @@
+use crate::proto::BlockScopedData;
+use anyhow::{anyhow, Context, Error};
+use clap::Args;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// Recording a sync to a file and syncing from such a file.
+#[derive(Args, Clone, Default)]
+pub struct CaptureArgs {
+    /// File to append the blocks received and the mapping values fetched to, to replay the sync with `--replay`
+    #[arg(long)]
+    pub capture: Option<String>,
+
+    /// File written with `--capture` to sync from instead of the endpoint, answering mapping value requests with the recorded values
+    #[arg(long, conflicts_with = "capture")]
+    pub replay: Option<String>,
+}
+
+/// A line of a capture file. The mapping values follow the block or the
+/// retry they were fetched for, in the order they were fetched.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Entry {
+    /// A block as received from the Substreams endpoint, protobuf encoded in hex.
+    Block {
+        data: String,
+    },
+    /// A failed record handled again.
+    Retry {
+        transition_id: String,
+    },
+    Mapping(CapturedMapping),
+}
+
+/// A mapping value request to the Aleo REST API and its outcome.
+#[derive(Serialize, Deserialize)]
+pub struct CapturedMapping {
+    pub program_id: String,
+    pub mapping_name: String,
+    pub mapping_key: String,
+    /// `None` for a `null` value or an error.
+    pub value: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Work done by `sync`, in the order it was done.
+pub enum Step {
+    Block(BlockScopedData),
+    Retry { transition_id: String },
+}
+
+/// A step of a capture and the mapping values fetched for it.
+pub struct ReplayStep {
+    pub step: Step,
+    pub mappings: VecDeque<CapturedMapping>,
+}
+
+/// Appends what `sync` does to a capture file, one JSON object per line.
+/// The blocks and retries are recorded while holding the sinks, so that the
+/// file follows the order they were written in.
+pub struct CaptureWriter {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl CaptureWriter {
+    pub fn open(path: PathBuf) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("open {}", path.display()))?;
+        Ok(CaptureWriter {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn block(&self, data: &BlockScopedData) -> Result<(), Error> {
+        self.write(&Entry::Block {
+            data: hex::encode(data.encode_to_vec()),
+        })
+    }
+
+    pub fn retry(&self, transition_id: &str) -> Result<(), Error> {
+        self.write(&Entry::Retry {
+            transition_id: transition_id.to_string(),
+        })
+    }
+
+    pub fn mapping(
+        &self,
+        program_id: &str,
+        mapping_name: &str,
+        mapping_key: &str,
+        value: &Result<Option<String>, Error>,
+    ) -> Result<(), Error> {
+        self.write(&Entry::Mapping(CapturedMapping {
+            program_id: program_id.to_string(),
+            mapping_name: mapping_name.to_string(),
+            mapping_key: mapping_key.to_string(),
+            value: value.as_ref().ok().cloned().flatten(),
+            error: value.as_ref().err().map(|err| format!("{:#}", err)),
+        }))
+    }
+
+    fn write(&self, entry: &Entry) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file
+            .lock()
+            .unwrap()
+            .write_all(&line)
+            .with_context(|| format!("write {}", self.path.display()))
+    }
+}
+
+/// Reads the steps of a capture file.
+pub fn read(path: &str) -> Result<Vec<ReplayStep>, Error> {
+    let content = fs::read_to_string(path).with_context(|| format!("read {}", path))?;
+
+    let mut steps: Vec<ReplayStep> = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry =
+            serde_json::from_str(line).with_context(|| format!("{} line {}", path, index + 1))?;
+        let step = match entry {
+            Entry::Block { data } => {
+                Step::Block(BlockScopedData::decode(hex::decode(data)?.as_slice())?)
+            }
+            Entry::Retry { transition_id } => Step::Retry { transition_id },
+            Entry::Mapping(mapping) => {
+                steps
+                    .last_mut()
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "{} line {}: mapping value before any block",
+                            path,
+                            index + 1
+                        )
+                    })?
+                    .mappings
+                    .push_back(mapping);
+                continue;
+            }
+        };
+        steps.push(ReplayStep {
+            step,
+            mappings: VecDeque::new(),
+        });
+    }
+
+    Ok(steps)
+}
+
+/// Mapping values answered instead of the Aleo REST API while replaying,
+/// those of the current step in the order they were fetched.
+#[derive(Clone, Default)]
+pub struct ReplayedMappings(Arc<Mutex<VecDeque<CapturedMapping>>>);
+
+impl ReplayedMappings {
+    /// Replaces the values with those of the next step. Returns how many
+    /// values of the previous step weren't requested.
+    pub fn load(&self, mappings: VecDeque<CapturedMapping>) -> usize {
+        std::mem::replace(&mut *self.0.lock().unwrap(), mappings).len()
+    }
+
+    /// The next value, which must have been captured for the same request.
+    pub fn next(
+        &self,
+        program_id: &str,
+        mapping_name: &str,
+        mapping_key: &str,
+    ) -> Result<Option<String>, Error> {
+        let mapping = self.0.lock().unwrap().pop_front().ok_or_else(|| {
+            anyhow!(
+                "Replay has no captured value left for {}[{}] of {}",
+                mapping_name,
+                mapping_key,
+                program_id
+            )
+        })?;
+        if mapping.program_id != program_id
+            || mapping.mapping_name != mapping_name
+            || mapping.mapping_key != mapping_key
+        {
+            return Err(anyhow!(
+                "Replay diverged, requested {}[{}] of {} but captured {}[{}] of {}",
+                mapping_name,
+                mapping_key,
+                program_id,
+                mapping.mapping_name,
+                mapping.mapping_key,
+                mapping.program_id
+            ));
+        }
+
+        match mapping.error {
+            Some(error) => Err(anyhow!(error)),
+            None => Ok(mapping.value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{proto::Records, substreams_mock};
+    use std::{env, process};
+
+    #[test]
+    fn replays_captured_steps() {
+        let path = env::temp_dir().join(format!("capture-{}.ndjson", process::id()));
+        let _ = fs::remove_file(&path);
+        let block = substreams_mock::block(
+            7,
+            "cursor-7",
+            crate::proto::ForkStep::StepNew,
+            &Records::default(),
+        );
+
+        let capture_writer = CaptureWriter::open(path.clone()).unwrap();
+        capture_writer.block(&block).unwrap();
+        capture_writer
+            .mapping(
+                "p.aleo",
+                "daos",
+                "1u64",
+                &Ok(Some("{ id: 1u64 }".to_string())),
+            )
+            .unwrap();
+        capture_writer
+            .mapping("p.aleo", "daos", "2u64", &Ok(None))
+            .unwrap();
+        capture_writer.retry("at1retried").unwrap();
+        capture_writer
+            .mapping("p.aleo", "votes", "1u64", &Err(anyhow!("timed out")))
+            .unwrap();
+
+        let mut steps = read(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(&steps[0].step, Step::Block(data) if *data == block));
+        assert!(
+            matches!(&steps[1].step, Step::Retry { transition_id } if transition_id == "at1retried")
+        );
+
+        let replayed_mappings = ReplayedMappings::default();
+        replayed_mappings.load(std::mem::take(&mut steps[0].mappings));
+        assert_eq!(
+            replayed_mappings.next("p.aleo", "daos", "1u64").unwrap(),
+            Some("{ id: 1u64 }".to_string())
+        );
+        // Requests that differ from the capture fail instead of being answered
+        // with the value of another entry.
+        assert!(replayed_mappings.next("p.aleo", "daos", "3u64").is_err());
+
+        assert_eq!(
+            replayed_mappings.load(std::mem::take(&mut steps[1].mappings)),
+            0
+        );
+        let err = replayed_mappings
+            .next("p.aleo", "votes", "1u64")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "timed out");
+        assert!(replayed_mappings.next("p.aleo", "votes", "1u64").is_err());
+    }
+}
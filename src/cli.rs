@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use crate::{
+    capture::CaptureArgs, export::ExportFormat, rest_api::RestApiArgs, sink::SinkKind,
+    telemetry::TelemetryArgs,
+};
+use clap::{Args, Parser, Subcommand};
 
 /// Simple programvscode-file://vscode-app/Applications/Visual%20Studio%20Code.app/Contents/Resources/app/out/vs/code/electron-sandbox/workbench/workbench.html to greet a person
 #[derive(Parser)]
@@ -6,6 +10,12 @@ use clap::{Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
+
+    #[command(flatten)]
+    pub telemetry: TelemetryArgs,
+
+    #[command(flatten)]
+    pub rest_api: RestApiArgs,
 }
 
 #[derive(Subcommand)]
@@ -32,9 +42,22 @@ pub enum Commands {
         #[arg(short = 't', long, default_value_t = u64::MAX)]
         end_block: u64,
 
-        /// Aleo REST API
-        #[arg(short, long)]
-        rest_api: String,
+        /// Aleo REST API, or a comma separated list of endpoints to fail over between
+        #[arg(short, long, required = true, value_delimiter = ',')]
+        rest_api: Vec<String>,
+
+        #[command(flatten)]
+        sinks: SinkArgs,
+
+        #[command(flatten)]
+        export: ExportSinkArgs,
+
+        #[command(flatten)]
+        capture: CaptureArgs,
+
+        /// Address to serve Prometheus metrics on at `/metrics`, e.g. `127.0.0.1:9100`
+        #[arg(long)]
+        metrics_addr: Option<String>,
     },
     /// Start query service.
     Serve {
@@ -46,9 +69,9 @@ pub enum Commands {
         #[arg(short = 'H', long, default_value_t = String::from("127.0.0.1"))]
         host: String,
 
-        /// Aleo REST API
-        #[arg(short, long)]
-        rest_api: String,
+        /// Aleo REST API, or a comma separated list of endpoints to fail over between
+        #[arg(short, long, required = true, value_delimiter = ',')]
+        rest_api: Vec<String>,
     },
 
     /// Start both `sync` and `serve` services simultaneously
@@ -81,8 +104,112 @@ pub enum Commands {
         #[arg(short = 'H', long, default_value_t = String::from("127.0.0.1"))]
         host: String,
 
-        /// Aleo REST API
+        /// Aleo REST API, or a comma separated list of endpoints to fail over between
+        #[arg(short, long, required = true, value_delimiter = ',')]
+        rest_api: Vec<String>,
+
+        #[command(flatten)]
+        sinks: SinkArgs,
+
+        #[command(flatten)]
+        export: ExportSinkArgs,
+
+        #[command(flatten)]
+        capture: CaptureArgs,
+    },
+
+    /// Check that indexed blocks link to each other through their previous hash
+    CheckChain {
+        /// First block height to check
+        #[arg(short, long, default_value_t = 0)]
+        start_block: i64,
+
+        /// Last block height to check
+        #[arg(short = 't', long, default_value_t = i64::MAX)]
+        end_block: i64,
+    },
+
+    /// Handle failed records again now, all but those given up on unless transition ids are given
+    Retry {
+        /// Aleo REST API, or a comma separated list of endpoints to fail over between
+        #[arg(short, long, required = true, value_delimiter = ',')]
+        rest_api: Vec<String>,
+
+        /// Transition ids of the failed records to retry, including those given up on
+        transition_ids: Vec<String>,
+    },
+
+    /// List the records whose handler failed, the earliest block first
+    ListFailures {
+        /// Maximum number of failed records to list
+        #[arg(short, long, default_value_t = 100, value_parser = clap::value_parser!(i64).range(1..))]
+        limit: i64,
+    },
+
+    /// Export tables to Parquet or CSV files, one per block range
+    Export {
+        /// Tables to export, e.g. `record,votes,proposals,balances`
+        #[arg(short = 'T', long, value_delimiter = ',', required = true)]
+        tables: Vec<String>,
+
+        /// Directory to write the files to
         #[arg(short, long)]
-        rest_api: String,
+        out_dir: String,
+
+        /// File format
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Parquet)]
+        format: ExportFormat,
+
+        /// Number of blocks per file
+        #[arg(long, default_value_t = 100_000, value_parser = clap::value_parser!(i64).range(1..))]
+        partition_size: i64,
+
+        /// First block height to export
+        #[arg(short, long, default_value_t = 0)]
+        start_block: i64,
+
+        /// Last block height to export
+        #[arg(short = 't', long, default_value_t = i64::MAX)]
+        end_block: i64,
     },
 }
+
+/// Where synced blocks are written.
+#[derive(Args, Clone)]
+pub struct SinkArgs {
+    /// Sinks to write blocks to
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "database")]
+    pub sinks: Vec<SinkKind>,
+
+    /// File the `ndjson` sink appends to
+    #[arg(long, default_value_t = String::from("records.ndjson"))]
+    pub ndjson_path: String,
+
+    /// Brokers of the `kafka` sink
+    #[arg(long, value_delimiter = ',', default_value = "localhost:9092")]
+    pub kafka_brokers: Vec<String>,
+
+    /// Topic of the `kafka` sink
+    #[arg(long, default_value_t = String::from("nexus-dao-records"))]
+    pub kafka_topic: String,
+}
+
+/// Exports block ranges while syncing, see `export`.
+#[derive(Args, Clone)]
+pub struct ExportSinkArgs {
+    /// Directory to export synced block ranges to, disabled when not set
+    #[arg(long)]
+    pub export_dir: Option<String>,
+
+    /// File format of the exports
+    #[arg(long, value_enum, default_value_t = ExportFormat::Parquet)]
+    pub export_format: ExportFormat,
+
+    /// Tables to export
+    #[arg(long, value_delimiter = ',', default_value = "record")]
+    pub export_tables: Vec<String>,
+
+    /// Number of blocks per exported file
+    #[arg(long, default_value_t = 100_000, value_parser = clap::value_parser!(i64).range(1..))]
+    pub export_partition_size: i64,
+}
@@ -1,28 +1,41 @@
-use crate::models::NewRecord;
+use crate::amount::Amount;
+use crate::models::{
+    Blocks, ChainBreak, Cursors, FailedRecords, NewBlocks, NewCursors, NewFailedRecords, NewRecord,
+    NewRecordInput, NewRecordOutput, NewTransactions, NewViewKeys, RecordFilter, SpendingInput,
+    StoredRecordOutput, Transactions, ViewKeys,
+};
 use crate::proto::Records;
 use crate::schema::balances::dsl::balances;
 use crate::schema::balances::key;
 use crate::{
     models::{
-        AutoIncrement, Balances, Daos, ExtendPledgePeriod, Input, NewAutoIncrement, NewBalances,
-        NewDaos, NewExtendPledgePeriod, NewProfiles, NewProposals, NewStakeAmounts, NewToken,
-        NewTokenInfos, NewVotes, Output, Profiles, Proposals, Record, StakeAmounts, Token,
-        TokenInfos, Votes,
+        AutoIncrement, Balances, DaoTotal, Daos, ExtendPledgePeriod, Input, NewAutoIncrement,
+        NewBalances, NewDaos, NewExtendPledgePeriod, NewProfiles, NewProposals, NewStakeAmounts,
+        NewToken, NewTokenInfos, NewVotes, Output, Profiles, Proposals, Record, RespTotal,
+        StakeAmounts, Token, TokenInfoTotal, TokenInfos, VoteTotals, Votes,
     },
     schema::{self},
 };
-use anyhow::{Error, Ok};
+use anyhow::{anyhow, Error, Ok};
 use diesel::{
-    r2d2::{ConnectionManager, PoolError},
-    BoolExpressionMethods, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl,
-    SelectableHelper,
+    dsl::{count_distinct, sum},
+    r2d2::ConnectionManager,
+    sql_types::{Array, BigInt},
+    upsert::excluded,
+    BoolExpressionMethods, ExpressionMethods, JoinOnDsl, OptionalExtension, PgConnection,
+    PgJsonbExpressionMethods, QueryDsl, QueryableByName, RunQueryDsl, SelectableHelper,
 };
+use diesel_migrations::{embed_migrations, EmbeddedMigrations};
 use lazy_static::lazy_static;
 use r2d2::{Pool, PooledConnection};
-use std::env;
+use std::{collections::BTreeMap, env, str::FromStr, time::Duration};
 
 pub type PgPool = Pool<ConnectionManager<PgConnection>>;
 
+/// Postgres schema, applied with the Diesel CLI. Embedded to check that the
+/// database is up to date.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
 lazy_static! {
     pub static ref POOL: Pool<ConnectionManager<PgConnection>> = create_pg_pool().unwrap();
 }
@@ -31,7 +44,7 @@ pub fn batch_insert_records(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
     records: &Records,
 ) -> Result<(), Error> {
-    use schema::record;
+    use schema::{record, record_inputs, record_outputs};
 
     for record in records.records.iter() {
         let inputs = record
@@ -59,8 +72,8 @@ pub fn batch_insert_records(
         let new_record = NewRecord {
             program: &record.program,
             function: &record.function,
-            inputs: &serde_json::to_string(&inputs).unwrap(),
-            outputs: &serde_json::to_string(&outputs).unwrap(),
+            inputs: serde_json::to_value(&inputs)?,
+            outputs: serde_json::to_value(&outputs)?,
             block_hash: &record.block_hash,
             previous_hash: &record.previous_hash,
             transaction_id: &record.transaction_id,
@@ -70,13 +83,352 @@ pub fn batch_insert_records(
             timestamp: record.timestamp as i64,
         };
 
+        let new_inputs = inputs
+            .iter()
+            .enumerate()
+            .map(|(position, input)| NewRecordInput {
+                transition_id: &record.transition_id,
+                position: position as i64,
+                r#type: &input.r#type,
+                id: &input.id,
+                value: &input.value,
+                tag: &input.tag,
+            })
+            .collect::<Vec<NewRecordInput>>();
+
+        let new_outputs = outputs
+            .iter()
+            .enumerate()
+            .map(|(position, output)| NewRecordOutput {
+                transition_id: &record.transition_id,
+                position: position as i64,
+                r#type: &output.r#type,
+                id: &output.id,
+                checksum: &output.checksum,
+                value: &output.value,
+            })
+            .collect::<Vec<NewRecordOutput>>();
+
         diesel::insert_into(record::table)
             .values(&new_record)
             .on_conflict(record::transition_id)
             .do_nothing()
             .execute(conn)?;
+
+        diesel::insert_into(record_inputs::table)
+            .values(&new_inputs)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        diesel::insert_into(record_outputs::table)
+            .values(&new_outputs)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
+
+/// Records the block `records` belong to, with one row per transaction. The
+/// hashes are taken from the transitions when there are any so that they can
+/// be compared with their `previous_hash`.
+pub fn insert_block(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_height: i64,
+    param_block_hash: &str,
+    param_timestamp: i64,
+    records: &Records,
+) -> Result<(), Error> {
+    use schema::{blocks, transactions};
+
+    let mut transition_counts: BTreeMap<&str, i64> = BTreeMap::new();
+    for record in records.records.iter() {
+        *transition_counts.entry(&record.transaction_id).or_default() += 1;
+    }
+
+    let first_record = records.records.first();
+    let new_block = NewBlocks {
+        height: param_height,
+        block_hash: first_record.map_or(param_block_hash, |record| &record.block_hash),
+        previous_hash: first_record.map(|record| record.previous_hash.as_str()),
+        timestamp: first_record.map_or(param_timestamp, |record| record.timestamp),
+        transaction_count: transition_counts.len() as i64,
+        transition_count: records.records.len() as i64,
+    };
+
+    let new_transactions = transition_counts
+        .iter()
+        .map(|(param_transaction_id, count)| NewTransactions {
+            transaction_id: param_transaction_id,
+            height: param_height,
+            transition_count: *count,
+        })
+        .collect::<Vec<NewTransactions>>();
+
+    diesel::insert_into(blocks::table)
+        .values(&new_block)
+        .on_conflict(blocks::height)
+        .do_update()
+        .set((
+            blocks::block_hash.eq(excluded(blocks::block_hash)),
+            blocks::previous_hash.eq(excluded(blocks::previous_hash)),
+            blocks::timestamp.eq(excluded(blocks::timestamp)),
+            blocks::transaction_count.eq(excluded(blocks::transaction_count)),
+            blocks::transition_count.eq(excluded(blocks::transition_count)),
+        ))
+        .execute(conn)?;
+
+    diesel::insert_into(transactions::table)
+        .values(&new_transactions)
+        .on_conflict(transactions::transaction_id)
+        .do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Deletes the blocks from `param_height` on with their transactions,
+/// records and token records, and unspends the tokens they spent.
+pub fn delete_blocks_from(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_height: i64,
+) -> Result<(), Error> {
+    use schema::{blocks, failed_records, record, token};
+
+    diesel::delete(token::table.filter(token::created_at_height.ge(param_height))).execute(conn)?;
+    diesel::update(token::table.filter(token::spent_at_height.ge(param_height)))
+        .set((
+            token::serial_number.eq(None::<String>),
+            token::spent_at_height.eq(None::<i64>),
+            token::spent_by_transition_id.eq(None::<String>),
+        ))
+        .execute(conn)?;
+    diesel::delete(failed_records::table.filter(failed_records::height.ge(param_height)))
+        .execute(conn)?;
+    // Record inputs and outputs, and transactions, are deleted in cascade.
+    diesel::delete(record::table.filter(record::height.ge(param_height))).execute(conn)?;
+    diesel::delete(blocks::table.filter(blocks::height.ge(param_height))).execute(conn)?;
+
+    Ok(())
+}
+
+pub fn get_block_by_height(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_height: i64,
+) -> Result<Option<Blocks>, Error> {
+    use schema::blocks::dsl::*;
+
+    let block = blocks
+        .filter(height.eq(param_height))
+        .select(Blocks::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(block)
+}
+
+pub fn get_latest_block(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Option<Blocks>, Error> {
+    use schema::blocks::dsl::*;
+
+    let block = blocks
+        .order(height.desc())
+        .select(Blocks::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(block)
+}
+
+pub fn get_transactions_by_height(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_height: i64,
+) -> Result<Vec<Transactions>, Error> {
+    use schema::transactions::dsl::*;
+
+    let ret_transactions = transactions
+        .filter(height.eq(param_height))
+        .order(transaction_id.asc())
+        .select(Transactions::as_select())
+        .load(conn)?;
+
+    Ok(ret_transactions)
+}
+
+/// Blocks in the range whose `previous_hash` isn't the hash of the block below.
+/// Blocks without a known `previous_hash` or without an indexed parent are
+/// skipped, see `get_chain_gaps`.
+pub fn get_chain_breaks(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    start_block: i64,
+    end_block: i64,
+) -> Result<Vec<ChainBreak>, Error> {
+    let breaks = diesel::sql_query(
+        "SELECT block.height, block.previous_hash, parent.block_hash AS parent_hash \
+        FROM blocks block JOIN blocks parent ON parent.height = block.height - 1 \
+        WHERE block.height BETWEEN $1 AND $2 AND block.previous_hash <> parent.block_hash \
+        ORDER BY block.height",
+    )
+    .bind::<BigInt, _>(start_block)
+    .bind::<BigInt, _>(end_block)
+    .load(conn)?;
+
+    Ok(breaks)
+}
+
+/// Heights in the range of blocks indexed without the block below them, other
+/// than the first indexed block.
+pub fn get_chain_gaps(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    start_block: i64,
+    end_block: i64,
+) -> Result<Vec<i64>, Error> {
+    #[derive(QueryableByName)]
+    struct Gap {
+        #[diesel(sql_type = BigInt)]
+        height: i64,
     }
 
+    let gaps: Vec<Gap> = diesel::sql_query(
+        "SELECT block.height FROM blocks block \
+        WHERE block.height BETWEEN $1 AND $2 \
+        AND block.height > (SELECT MIN(height) FROM blocks) \
+        AND NOT EXISTS (SELECT 1 FROM blocks parent WHERE parent.height = block.height - 1) \
+        ORDER BY block.height",
+    )
+    .bind::<BigInt, _>(start_block)
+    .bind::<BigInt, _>(end_block)
+    .load(conn)?;
+    let gaps = gaps.into_iter().map(|gap| gap.height).collect();
+
+    Ok(gaps)
+}
+
+pub fn get_cursor_by_name(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_name: &str,
+) -> Result<Option<Cursors>, Error> {
+    use schema::cursors::dsl::*;
+
+    let ret_cursor = cursors
+        .filter(name.eq(param_name))
+        .select(Cursors::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(ret_cursor)
+}
+
+pub fn upsert_cursor(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_cursor: &Cursors,
+) -> Result<(), Error> {
+    use schema::cursors;
+
+    let new_cursor = NewCursors {
+        name: &param_cursor.name,
+        cursor: &param_cursor.cursor,
+        height: param_cursor.height,
+        block_hash: &param_cursor.block_hash,
+    };
+
+    diesel::insert_into(cursors::table)
+        .values(&new_cursor)
+        .on_conflict(cursors::name)
+        .do_update()
+        .set((
+            cursors::cursor.eq(excluded(cursors::cursor)),
+            cursors::height.eq(excluded(cursors::height)),
+            cursors::block_hash.eq(excluded(cursors::block_hash)),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Failed records due for a retry at `due_at`, the earliest block first.
+pub fn get_failed_records(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    due_at: i64,
+    limit: i64,
+    include_gave_up: bool,
+) -> Result<Vec<FailedRecords>, Error> {
+    use schema::failed_records::dsl::*;
+
+    let mut query = failed_records.filter(next_retry_at.le(due_at)).into_boxed();
+    if !include_gave_up {
+        query = query.filter(gave_up.eq(false));
+    }
+    let ret_failed_records = query
+        .order((height.asc(), transition_id.asc()))
+        .limit(limit)
+        .select(FailedRecords::as_select())
+        .load(conn)?;
+
+    Ok(ret_failed_records)
+}
+
+pub fn get_failed_record_by_transition_id(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_transition_id: &str,
+) -> Result<Option<FailedRecords>, Error> {
+    use schema::failed_records::dsl::*;
+
+    let ret_failed_record = failed_records
+        .filter(transition_id.eq(param_transition_id))
+        .select(FailedRecords::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(ret_failed_record)
+}
+
+pub fn upsert_failed_record(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_failed_record: &FailedRecords,
+) -> Result<(), Error> {
+    use schema::failed_records;
+
+    let new_failed_record = NewFailedRecords {
+        transition_id: &param_failed_record.transition_id,
+        program: &param_failed_record.program,
+        function: &param_failed_record.function,
+        height: param_failed_record.height,
+        record: &param_failed_record.record,
+        error: &param_failed_record.error,
+        attempts: param_failed_record.attempts,
+        first_failed_at: param_failed_record.first_failed_at,
+        last_failed_at: param_failed_record.last_failed_at,
+        next_retry_at: param_failed_record.next_retry_at,
+        gave_up: param_failed_record.gave_up,
+    };
+
+    diesel::insert_into(failed_records::table)
+        .values(&new_failed_record)
+        .on_conflict(failed_records::transition_id)
+        .do_update()
+        .set((
+            failed_records::record.eq(excluded(failed_records::record)),
+            failed_records::error.eq(excluded(failed_records::error)),
+            failed_records::attempts.eq(excluded(failed_records::attempts)),
+            failed_records::last_failed_at.eq(excluded(failed_records::last_failed_at)),
+            failed_records::next_retry_at.eq(excluded(failed_records::next_retry_at)),
+            failed_records::gave_up.eq(excluded(failed_records::gave_up)),
+        ))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn delete_failed_record(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_transition_id: &str,
+) -> Result<(), Error> {
+    use schema::failed_records::dsl::*;
+
+    diesel::delete(failed_records.filter(transition_id.eq(param_transition_id))).execute(conn)?;
+
     Ok(())
 }
 
@@ -216,36 +568,66 @@ pub fn get_stakes_by_owner(
     Ok(ret_stakes)
 }
 
+pub fn get_balance_by_key(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_key: String,
+) -> Result<Option<Balances>, Error> {
+    use schema::balances::dsl::*;
+
+    let ret_balance = balances
+        .filter(key.eq(param_key))
+        .select(Balances::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(ret_balance)
+}
+
+pub fn get_stake_amount_by_key(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_key: String,
+) -> Result<Option<StakeAmounts>, Error> {
+    use schema::stake_amounts::dsl::*;
+
+    let ret_stake = stake_amounts
+        .filter(key.eq(param_key))
+        .select(StakeAmounts::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(ret_stake)
+}
+
 pub fn get_vote_by_key(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
     param_key: String,
-) -> Result<Votes, Error> {
+) -> Result<Option<Votes>, Error> {
     use schema::votes::dsl::*;
 
-    let mut ret_votes: Vec<Votes> = votes
+    let ret_vote = votes
         .filter(key.eq(param_key))
         .select(Votes::as_select())
-        .load(conn)
-        .expect("Error loading balances");
+        .first(conn)
+        .optional()?;
 
-    if ret_votes.is_empty() {
-        return Err(Error::msg("The votes was not found"));
-    }
-
-    Ok(ret_votes.pop().unwrap())
+    Ok(ret_vote)
 }
 
 pub fn get_vote_by_voter(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
     param_voter: String,
+    param_offset: i64,
+    param_limit: i64,
 ) -> Result<Vec<Votes>, Error> {
     use schema::votes::dsl::*;
 
     let ret_votes: Vec<Votes> = votes
         .filter(voter.eq(param_voter))
+        .order((time.desc(), key.asc()))
+        .offset(param_offset)
+        .limit(param_limit)
         .select(Votes::as_select())
-        .load(conn)
-        .expect("Error loading votes");
+        .load(conn)?;
 
     Ok(ret_votes)
 }
@@ -294,65 +676,193 @@ pub fn get_pledgers_by_token_info_id(
 ) -> Result<i64, Error> {
     use schema::stake_amounts::dsl::*;
 
-    let stake: Vec<StakeAmounts> = stake_amounts
+    let pledgers = stake_amounts
         .filter(token_info_id.eq(param_token_info_id))
-        .select(StakeAmounts::as_select())
-        .distinct_on(owner)
-        .load(conn)
-        .expect("Error loading stakes");
+        .select(count_distinct(owner))
+        .get_result(conn)?;
 
-    Ok(stake.len().to_string().parse::<i64>().unwrap())
+    Ok(pledgers)
 }
 
 pub fn get_pledgers_total(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
-) -> Result<String, Error> {
+) -> Result<i64, Error> {
     use schema::stake_amounts::dsl::*;
 
-    let stake: Vec<StakeAmounts> = stake_amounts
-        .select(StakeAmounts::as_select())
-        .distinct_on(owner)
-        .load(conn)
-        .expect("Error loading stakes");
+    let pledgers = stake_amounts
+        .select(count_distinct(owner))
+        .get_result(conn)?;
+
+    Ok(pledgers)
+}
+
+pub fn get_pledgers_by_token_info(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Vec<TokenInfoTotal>, Error> {
+    use schema::stake_amounts::dsl::*;
 
-    let count = stake.len();
-    Ok(count.to_string())
+    let rows: Vec<(i64, i64)> = stake_amounts
+        .group_by(token_info_id)
+        .select((token_info_id, count_distinct(owner)))
+        .order(token_info_id.asc())
+        .load(conn)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(param_token_info_id, pledgers)| TokenInfoTotal {
+            token_info_id: param_token_info_id,
+            total: pledgers.into(),
+        })
+        .collect())
+}
+
+pub fn get_pledgers_by_dao(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Vec<DaoTotal>, Error> {
+    use schema::{stake_amounts, token_infos};
+
+    let rows: Vec<(i64, i64)> = stake_amounts::table
+        .inner_join(token_infos::table.on(token_infos::id.eq(stake_amounts::token_info_id)))
+        .group_by(token_infos::dao_id)
+        .select((token_infos::dao_id, count_distinct(stake_amounts::owner)))
+        .order(token_infos::dao_id.asc())
+        .load(conn)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(dao_id, pledgers)| DaoTotal {
+            dao_id,
+            total: pledgers.into(),
+        })
+        .collect())
 }
 
 pub fn get_stake_funds_total(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
-) -> Result<String, Error> {
+) -> Result<Amount, Error> {
     use schema::stake_amounts::dsl::*;
 
-    let stake: Vec<StakeAmounts> = stake_amounts
-        .select(StakeAmounts::as_select())
-        .load(conn)
-        .expect("Error loading stakes");
+    let total: Option<Amount> = stake_amounts.select(sum(amount)).get_result(conn)?;
 
-    let mut count: i64 = 0;
-    for i in stake {
-        count = count + i.amount
-    }
+    Ok(total.unwrap_or_default())
+}
 
-    Ok(count.to_string())
+pub fn get_stake_funds_by_token_info(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Vec<TokenInfoTotal>, Error> {
+    use schema::stake_amounts::dsl::*;
+
+    let rows: Vec<(i64, Option<Amount>)> = stake_amounts
+        .group_by(token_info_id)
+        .select((token_info_id, sum(amount)))
+        .order(token_info_id.asc())
+        .load(conn)?;
+
+    token_info_totals(rows)
+}
+
+pub fn get_stake_funds_by_dao(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Vec<DaoTotal>, Error> {
+    use schema::{stake_amounts, token_infos};
+
+    let rows: Vec<(i64, Option<Amount>)> = stake_amounts::table
+        .inner_join(token_infos::table.on(token_infos::id.eq(stake_amounts::token_info_id)))
+        .group_by(token_infos::dao_id)
+        .select((token_infos::dao_id, sum(stake_amounts::amount)))
+        .order(token_infos::dao_id.asc())
+        .load(conn)?;
+
+    dao_totals(rows)
 }
 
 pub fn get_funds_total(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
-) -> Result<String, Error> {
+) -> Result<Amount, Error> {
     use schema::balances::dsl::*;
 
-    let stake: Vec<Balances> = balances
-        .select(Balances::as_select())
-        .load(conn)
-        .expect("Error loading stakes");
+    let total: Option<Amount> = balances.select(sum(amount)).get_result(conn)?;
 
-    let mut count: i64 = 0;
-    for i in stake {
-        count = count + i.amount
-    }
+    Ok(total.unwrap_or_default())
+}
+
+pub fn get_funds_by_token_info(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Vec<TokenInfoTotal>, Error> {
+    use schema::balances::dsl::*;
+
+    let rows: Vec<(i64, Option<Amount>)> = balances
+        .group_by(token_info_id)
+        .select((token_info_id, sum(amount)))
+        .order(token_info_id.asc())
+        .load(conn)?;
+
+    token_info_totals(rows)
+}
+
+pub fn get_funds_by_dao(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Vec<DaoTotal>, Error> {
+    use schema::{balances, token_infos};
+
+    let rows: Vec<(i64, Option<Amount>)> = balances::table
+        .inner_join(token_infos::table.on(token_infos::id.eq(balances::token_info_id)))
+        .group_by(token_infos::dao_id)
+        .select((token_infos::dao_id, sum(balances::amount)))
+        .order(token_infos::dao_id.asc())
+        .load(conn)?;
 
-    Ok(count.to_string())
+    dao_totals(rows)
+}
+
+pub fn get_pledgers_summary(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<RespTotal, Error> {
+    Ok(RespTotal {
+        total: get_pledgers_total(conn)?.into(),
+        by_token_info: get_pledgers_by_token_info(conn)?,
+        by_dao: get_pledgers_by_dao(conn)?,
+    })
+}
+
+pub fn get_stake_funds_summary(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<RespTotal, Error> {
+    Ok(RespTotal {
+        total: get_stake_funds_total(conn)?,
+        by_token_info: get_stake_funds_by_token_info(conn)?,
+        by_dao: get_stake_funds_by_dao(conn)?,
+    })
+}
+
+pub fn get_funds_summary(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<RespTotal, Error> {
+    Ok(RespTotal {
+        total: get_funds_total(conn)?,
+        by_token_info: get_funds_by_token_info(conn)?,
+        by_dao: get_funds_by_dao(conn)?,
+    })
+}
+
+fn token_info_totals(rows: Vec<(i64, Option<Amount>)>) -> Result<Vec<TokenInfoTotal>, Error> {
+    Ok(rows
+        .into_iter()
+        .map(|(token_info_id, total)| TokenInfoTotal {
+            token_info_id,
+            total: total.unwrap_or_default(),
+        })
+        .collect())
+}
+
+fn dao_totals(rows: Vec<(i64, Option<Amount>)>) -> Result<Vec<DaoTotal>, Error> {
+    Ok(rows
+        .into_iter()
+        .map(|(dao_id, total)| DaoTotal {
+            dao_id,
+            total: total.unwrap_or_default(),
+        })
+        .collect())
 }
 
 pub fn get_creating_dao_proposal_ids(
@@ -373,6 +883,21 @@ pub fn get_creating_dao_proposal_ids(
     Ok(prop)
 }
 
+pub fn get_proposal_by_id(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_id: i64,
+) -> Result<Option<Proposals>, Error> {
+    use schema::proposals::dsl::*;
+
+    let ret_prop = proposals
+        .filter(id.eq(param_id))
+        .select(Proposals::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(ret_prop)
+}
+
 pub fn get_proposals_by_proposal_id(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
     param_id: i64,
@@ -407,54 +932,636 @@ pub fn get_all_proposal_ids(
     Ok(prop)
 }
 
-fn create_pg_pool() -> Result<PgPool, PoolError> {
+pub fn get_records_page(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    start_block: i64,
+    end_block: i64,
+    filter: &RecordFilter,
+    param_offset: i64,
+    param_limit: i64,
+) -> Result<Vec<Record>, Error> {
+    use schema::record::dsl::*;
+    use schema::{record_inputs, record_outputs};
+
+    let mut query = record
+        .filter(height.between(start_block, end_block))
+        .into_boxed();
+
+    if let Some(param_id) = &filter.id {
+        query = query.filter(
+            transition_id
+                .eq_any(
+                    record_inputs::table
+                        .filter(record_inputs::id.eq(param_id))
+                        .select(record_inputs::transition_id),
+                )
+                .or(transition_id.eq_any(
+                    record_outputs::table
+                        .filter(record_outputs::id.eq(param_id))
+                        .select(record_outputs::transition_id),
+                )),
+        );
+    }
+
+    if let Some(param_value) = &filter.value {
+        query = query.filter(
+            transition_id
+                .eq_any(
+                    record_inputs::table
+                        .filter(record_inputs::value.eq(param_value))
+                        .select(record_inputs::transition_id),
+                )
+                .or(transition_id.eq_any(
+                    record_outputs::table
+                        .filter(record_outputs::value.eq(param_value))
+                        .select(record_outputs::transition_id),
+                )),
+        );
+    }
+
+    // Containment lets Postgres use the GIN indexes on the JSONB columns.
+    if let Some(param_type) = &filter.r#type {
+        let pattern = serde_json::json!([{ "type": param_type }]);
+        query = query.filter(
+            inputs
+                .contains(pattern.clone())
+                .or(outputs.contains(pattern)),
+        );
+    }
+
+    let records = query
+        .order((height.asc(), transition_id.asc()))
+        .offset(param_offset)
+        .limit(param_limit)
+        .select(Record::as_select())
+        .load(conn)?;
+
+    Ok(records)
+}
+
+pub fn get_daos_page(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_offset: i64,
+    param_limit: i64,
+) -> Result<Vec<Daos>, Error> {
+    use schema::daos::dsl::*;
+
+    let ret_daos = daos
+        .order(id.asc())
+        .offset(param_offset)
+        .limit(param_limit)
+        .select(Daos::as_select())
+        .load(conn)?;
+
+    Ok(ret_daos)
+}
+
+pub fn get_daos_by_ids(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_ids: &[i64],
+) -> Result<Vec<Daos>, Error> {
+    use schema::daos::dsl::*;
+
+    let ret_daos = daos
+        .filter(id.eq_any(param_ids))
+        .select(Daos::as_select())
+        .load(conn)?;
+
+    Ok(ret_daos)
+}
+
+pub fn get_daos_by_creators(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_creators: &[String],
+) -> Result<Vec<Daos>, Error> {
+    use schema::daos::dsl::*;
+
+    let ret_daos = daos
+        .filter(creator.eq_any(param_creators))
+        .order(id.asc())
+        .select(Daos::as_select())
+        .load(conn)?;
+
+    Ok(ret_daos)
+}
+
+pub fn get_proposals_page(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_offset: i64,
+    param_limit: i64,
+) -> Result<Vec<Proposals>, Error> {
+    use schema::proposals::dsl::*;
+
+    let prop = proposals
+        .order(id.asc())
+        .offset(param_offset)
+        .limit(param_limit)
+        .select(Proposals::as_select())
+        .load(conn)?;
+
+    Ok(prop)
+}
+
+pub fn get_proposals_by_ids(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_ids: &[i64],
+) -> Result<Vec<Proposals>, Error> {
+    use schema::proposals::dsl::*;
+
+    let prop = proposals
+        .filter(id.eq_any(param_ids))
+        .select(Proposals::as_select())
+        .load(conn)?;
+
+    Ok(prop)
+}
+
+/// The page of proposals of each DAO in `param_dao_ids`, as
+/// `get_dao_proposals_page` would return it.
+pub fn get_proposals_pages_by_dao_ids(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_dao_ids: &[i64],
+    param_offset: i64,
+    param_limit: i64,
+) -> Result<Vec<Proposals>, Error> {
+    let prop = diesel::sql_query(
+        "SELECT id, title, proposer, summary, body, dao_id, created, duration, type AS type_, \
+            adopt, reject, status, updated_at_height, updated_by_transition_id, \
+            updated_at_timestamp \
+        FROM (SELECT *, ROW_NUMBER() OVER (PARTITION BY dao_id ORDER BY id) AS position \
+            FROM proposals WHERE dao_id = ANY($1)) AS ranked \
+        WHERE position > $2 AND position <= $2 + $3 \
+        ORDER BY dao_id, id",
+    )
+    .bind::<Array<BigInt>, _>(param_dao_ids)
+    .bind::<BigInt, _>(param_offset)
+    .bind::<BigInt, _>(param_limit)
+    .load(conn)?;
+
+    Ok(prop)
+}
+
+pub fn get_dao_proposals_page(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_dao_id: i64,
+    param_offset: i64,
+    param_limit: i64,
+) -> Result<Vec<Proposals>, Error> {
+    use schema::proposals::dsl::*;
+
+    let prop = proposals
+        .filter(dao_id.eq(param_dao_id))
+        .order(id.asc())
+        .offset(param_offset)
+        .limit(param_limit)
+        .select(Proposals::as_select())
+        .load(conn)?;
+
+    Ok(prop)
+}
+
+/// The page of votes on each proposal in `param_proposal_ids`, as
+/// `get_proposal_votes_page` would return it.
+pub fn get_votes_pages_by_proposal_ids(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_proposal_ids: &[i64],
+    param_offset: i64,
+    param_limit: i64,
+) -> Result<Vec<Votes>, Error> {
+    let ret_votes = diesel::sql_query(
+        "SELECT key, voter, proposal_id, is_agreed, time, amount, updated_at_height, \
+            updated_by_transition_id, updated_at_timestamp \
+        FROM (SELECT *, ROW_NUMBER() OVER (PARTITION BY proposal_id ORDER BY time, key) \
+            AS position FROM votes WHERE proposal_id = ANY($1)) AS ranked \
+        WHERE position > $2 AND position <= $2 + $3 \
+        ORDER BY proposal_id, time, key",
+    )
+    .bind::<Array<BigInt>, _>(param_proposal_ids)
+    .bind::<BigInt, _>(param_offset)
+    .bind::<BigInt, _>(param_limit)
+    .load(conn)?;
+
+    Ok(ret_votes)
+}
+
+pub fn get_proposal_votes_page(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_proposal_id: i64,
+    param_offset: i64,
+    param_limit: i64,
+) -> Result<Vec<Votes>, Error> {
+    use schema::votes::dsl::*;
+
+    let ret_votes = votes
+        .filter(proposal_id.eq(param_proposal_id))
+        .order((time.asc(), key.asc()))
+        .offset(param_offset)
+        .limit(param_limit)
+        .select(Votes::as_select())
+        .load(conn)?;
+
+    Ok(ret_votes)
+}
+
+/// Number of agreeing and rejecting votes on a proposal along with the token
+/// amount backing each side.
+pub fn get_vote_totals_by_proposal_id(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_proposal_id: i64,
+) -> Result<VoteTotals, Error> {
+    let totals = diesel::sql_query(
+        "SELECT \
+            COUNT(*) FILTER (WHERE is_agreed) AS agree_count, \
+            COUNT(*) FILTER (WHERE NOT is_agreed) AS reject_count, \
+            COALESCE(SUM(amount) FILTER (WHERE is_agreed), 0) AS agree_amount, \
+            COALESCE(SUM(amount) FILTER (WHERE NOT is_agreed), 0) AS reject_amount \
+        FROM votes WHERE proposal_id = $1",
+    )
+    .bind::<BigInt, _>(param_proposal_id)
+    .get_result(conn)?;
+
+    Ok(totals)
+}
+
+pub fn get_token_infos_page(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_offset: i64,
+    param_limit: i64,
+) -> Result<Vec<TokenInfos>, Error> {
+    use schema::token_infos::dsl::*;
+
+    let ret_token_infos = token_infos
+        .order(id.asc())
+        .offset(param_offset)
+        .limit(param_limit)
+        .select(TokenInfos::as_select())
+        .load(conn)?;
+
+    Ok(ret_token_infos)
+}
+
+pub fn get_token_infos_by_ids(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_ids: &[i64],
+) -> Result<Vec<TokenInfos>, Error> {
+    use schema::token_infos::dsl::*;
+
+    let ret_token_infos = token_infos
+        .filter(id.eq_any(param_ids))
+        .select(TokenInfos::as_select())
+        .load(conn)?;
+
+    Ok(ret_token_infos)
+}
+
+pub fn get_profiles_by_addresses(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_addresses: &[String],
+) -> Result<Vec<Profiles>, Error> {
+    use schema::profiles::dsl::*;
+
+    let ret_profiles = profiles
+        .filter(address.eq_any(param_addresses))
+        .select(Profiles::as_select())
+        .load(conn)?;
+
+    Ok(ret_profiles)
+}
+
+/// Pool settings, overridable with the `DATABASE_POOL_SIZE`,
+/// `DATABASE_POOL_MIN_IDLE` and `DATABASE_ACQUIRE_TIMEOUT_SECS` environment variables.
+const DEFAULT_POOL_SIZE: u32 = 10;
+const DEFAULT_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+
+pub struct PoolSettings {
+    max_size: u32,
+    min_idle: Option<u32>,
+    acquire_timeout: Duration,
+}
+
+impl PoolSettings {
+    /// Reads the settings from the environment, failing on a value that
+    /// doesn't parse or is out of range.
+    pub fn from_env() -> Result<Self, Error> {
+        let max_size = env_or("DATABASE_POOL_SIZE", DEFAULT_POOL_SIZE)?;
+        if max_size < 1 {
+            return Err(anyhow!("DATABASE_POOL_SIZE must be at least 1"));
+        }
+        let min_idle = env_opt("DATABASE_POOL_MIN_IDLE")?;
+        if min_idle.map_or(false, |min_idle| min_idle > max_size) {
+            return Err(anyhow!(
+                "DATABASE_POOL_MIN_IDLE must not be larger than DATABASE_POOL_SIZE ({})",
+                max_size
+            ));
+        }
+        let acquire_timeout = env_or(
+            "DATABASE_ACQUIRE_TIMEOUT_SECS",
+            DEFAULT_ACQUIRE_TIMEOUT_SECS,
+        )?;
+        if acquire_timeout < 1 {
+            return Err(anyhow!("DATABASE_ACQUIRE_TIMEOUT_SECS must be at least 1"));
+        }
+
+        Ok(PoolSettings {
+            max_size,
+            min_idle,
+            acquire_timeout: Duration::from_secs(acquire_timeout),
+        })
+    }
+}
+
+fn create_pg_pool() -> Result<PgPool, Error> {
     let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let manager = ConnectionManager::<PgConnection>::new(db_url);
-    PgPool::builder().build(manager)
+    let settings = PoolSettings::from_env()?;
+    Ok(PgPool::builder()
+        .max_size(settings.max_size)
+        .min_idle(settings.min_idle)
+        .connection_timeout(settings.acquire_timeout)
+        // Checks connections with a `SELECT 1` before handing them out so a
+        // restarted database doesn't surface as query errors.
+        .test_on_check_out(true)
+        .build(manager)?)
+}
+
+/// Value of the environment variable `name`, `default` when it isn't set.
+fn env_or<T: FromStr>(name: &str, default: T) -> Result<T, Error> {
+    Ok(env_opt(name)?.unwrap_or(default))
+}
+
+fn env_opt<T: FromStr>(name: &str) -> Result<Option<T>, Error> {
+    match env::var(name) {
+        Result::Ok(value) => match value.parse() {
+            Result::Ok(value) => Ok(Some(value)),
+            Err(_) => Err(anyhow!("{} has an invalid value: {:?}", name, value)),
+        },
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(err) => Err(anyhow!("{}: {}", name, err)),
+    }
+}
+
+/// Runs blocking Diesel queries on Tokio's blocking thread pool with a
+/// connection from `POOL`, so async handlers never block a runtime worker.
+pub async fn with_conn<F, T>(f: F) -> Result<T, Error>
+where
+    F: FnOnce(&mut PooledConnection<ConnectionManager<PgConnection>>) -> Result<T, Error>
+        + Send
+        + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let mut conn = POOL.get()?;
+        f(&mut conn)
+    })
+    .await?
+}
+
+pub fn get_token_by_commitment(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_commitment: &str,
+) -> Result<Option<Token>, Error> {
+    use schema::token::dsl::*;
+
+    let ret_token = token
+        .filter(commitment.eq(param_commitment))
+        .select(Token::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(ret_token)
+}
+
+/// Token records of `param_owner` ordered by creation, the spent ones only
+/// when `include_spent` is set.
+pub fn get_tokens_by_owner(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_owner: &str,
+    include_spent: bool,
+) -> Result<Vec<Token>, Error> {
+    use schema::token::dsl::*;
+
+    let mut query = token
+        .filter(owner.eq(param_owner))
+        .order((created_at_height, commitment))
+        .select(Token::as_select())
+        .into_boxed();
+    if !include_spent {
+        query = query.filter(spent_at_height.is_null());
+    }
+
+    Ok(query.load(conn)?)
 }
 
+/// Inserts a token record, records are immutable so an existing row is kept.
+/// It only gets the tag when it had none, once its owner registered.
 pub fn insert_token(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
-    param_token: Token,
-) -> Result<String, Error> {
+    param_token: &Token,
+) -> Result<(), Error> {
     use schema::token;
 
     let new_token = NewToken {
+        commitment: &param_token.commitment,
         owner: &param_token.owner,
-        gates: param_token.gates,
+        gates: param_token.gates.clone(),
         token_info_id: param_token.token_info_id,
-        amount: param_token.amount,
+        amount: param_token.amount.clone(),
         expires: param_token.expires,
         staked_at: param_token.staked_at,
+        function: &param_token.function,
+        created_at_height: param_token.created_at_height,
+        created_by_transition_id: &param_token.created_by_transition_id,
+        created_at_timestamp: param_token.created_at_timestamp,
+        tag: param_token.tag.as_deref(),
+        serial_number: param_token.serial_number.as_deref(),
+        spent_at_height: param_token.spent_at_height,
+        spent_by_transition_id: param_token.spent_by_transition_id.as_deref(),
+        private: param_token.private,
     };
 
     diesel::insert_into(token::table)
         .values(&new_token)
-        .on_conflict(token::owner)
+        .on_conflict(token::commitment)
         .do_nothing()
         .execute(conn)?;
 
-    Ok("Insert successfully!".to_string())
+    if let Some(param_tag) = &param_token.tag {
+        diesel::update(
+            token::table
+                .filter(token::commitment.eq(&param_token.commitment))
+                .filter(token::tag.is_null()),
+        )
+        .set(token::tag.eq(param_tag))
+        .execute(conn)?;
+    }
+
+    Ok(())
 }
 
-pub fn update_token(
+/// Marks the unspent token record with tag `param_tag` as spent by the input
+/// with serial number `param_serial_number`.
+pub fn spend_token(
     conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
-    param_token: Token,
-) -> Result<String, Error> {
+    param_tag: &str,
+    param_serial_number: &str,
+    param_height: i64,
+    param_transition_id: &str,
+) -> Result<(), Error> {
     use schema::token::dsl::*;
 
-    diesel::update(token.filter(owner.eq(param_token.owner)))
+    diesel::update(
+        token
+            .filter(tag.eq(param_tag))
+            .filter(serial_number.is_null()),
+    )
+    .set((
+        serial_number.eq(param_serial_number),
+        spent_at_height.eq(param_height),
+        spent_by_transition_id.eq(param_transition_id),
+    ))
+    .execute(conn)?;
+
+    Ok(())
+}
+
+/// The first input consuming the record with `param_tag`.
+pub fn get_spending_input_by_tag(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_tag: &str,
+) -> Result<Option<SpendingInput>, Error> {
+    use schema::{record, record_inputs};
+
+    let ret_input = record_inputs::table
+        .inner_join(record::table)
+        .filter(record_inputs::tag.eq(param_tag))
+        .filter(record_inputs::type_.eq("record"))
+        .order((record::height, record_inputs::transition_id))
+        .select((
+            record_inputs::id,
+            record_inputs::transition_id,
+            record::height,
+        ))
+        .first::<(String, String, i64)>(conn)
+        .optional()?;
+
+    Ok(
+        ret_input.map(|(serial_number, transition_id, height)| SpendingInput {
+            serial_number,
+            transition_id,
+            height,
+        }),
+    )
+}
+
+/// The `record` outputs of the transitions of `param_program`, in the order
+/// they were created.
+pub fn get_record_outputs_by_program(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_program: &str,
+) -> Result<Vec<StoredRecordOutput>, Error> {
+    use schema::{record, record_outputs};
+
+    let rows = record_outputs::table
+        .inner_join(record::table)
+        .filter(record::program.eq(param_program))
+        .filter(record_outputs::type_.eq("record"))
+        .order((
+            record::height,
+            record_outputs::transition_id,
+            record_outputs::position,
+        ))
+        .select((
+            record_outputs::transition_id,
+            record::function,
+            record::height,
+            record::timestamp,
+            record_outputs::id,
+            record_outputs::value,
+        ))
+        .load::<(String, String, i64, i64, String, String)>(conn)?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(transition_id, function, height, timestamp, commitment, value)| StoredRecordOutput {
+                transition_id,
+                function,
+                height,
+                timestamp,
+                commitment,
+                value,
+            },
+        )
+        .collect())
+}
+
+pub fn get_view_keys(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+) -> Result<Vec<ViewKeys>, Error> {
+    use schema::view_keys::dsl::*;
+
+    Ok(view_keys
+        .order(address)
+        .select(ViewKeys::as_select())
+        .load(conn)?)
+}
+
+pub fn get_view_key_by_address(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_address: &str,
+) -> Result<Option<ViewKeys>, Error> {
+    use schema::view_keys::dsl::*;
+
+    let ret_view_key = view_keys
+        .filter(address.eq(param_address))
+        .select(ViewKeys::as_select())
+        .first(conn)
+        .optional()?;
+
+    Ok(ret_view_key)
+}
+
+/// Registers a view key. Registering it again replaces the access token and
+/// keeps the records already indexed for it.
+pub fn upsert_view_key(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_view_key: &ViewKeys,
+) -> Result<(), Error> {
+    use schema::view_keys;
+
+    let new_view_key = NewViewKeys {
+        address: &param_view_key.address,
+        encrypted_view_key: &param_view_key.encrypted_view_key,
+        access_token_hash: &param_view_key.access_token_hash,
+        registered_at: param_view_key.registered_at,
+        backfilled_at_height: param_view_key.backfilled_at_height,
+    };
+
+    diesel::insert_into(view_keys::table)
+        .values(&new_view_key)
+        .on_conflict(view_keys::address)
+        .do_update()
         .set((
-            gates.eq(param_token.gates),
-            token_info_id.eq(param_token.token_info_id),
-            amount.eq(param_token.amount),
-            expires.eq(param_token.expires),
-            staked_at.eq(param_token.staked_at),
+            view_keys::encrypted_view_key.eq(excluded(view_keys::encrypted_view_key)),
+            view_keys::access_token_hash.eq(excluded(view_keys::access_token_hash)),
         ))
-        .execute(conn)
-        .expect("Update: Error");
+        .execute(conn)?;
 
-    Ok("Update successfully!".to_string())
+    Ok(())
+}
+
+pub fn set_view_key_backfilled(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    param_address: &str,
+    param_height: i64,
+) -> Result<(), Error> {
+    use schema::view_keys::dsl::*;
+
+    diesel::update(view_keys.filter(address.eq(param_address)))
+        .set(backfilled_at_height.eq(param_height))
+        .execute(conn)?;
+
+    Ok(())
 }
 
 pub fn insert_token_info(
@@ -473,6 +1580,9 @@ pub fn insert_token_info(
         minted_amount: param_token_info.minted_amount,
         dao_id: param_token_info.dao_id,
         only_creator_can_mint: param_token_info.only_creator_can_mint,
+        updated_at_height: param_token_info.updated_at_height,
+        updated_by_transition_id: param_token_info.updated_by_transition_id.as_deref(),
+        updated_at_timestamp: param_token_info.updated_at_timestamp,
     };
 
     diesel::insert_into(token_infos::table)
@@ -520,6 +1630,9 @@ pub fn update_token_info(
             minted_amount.eq(param_token_info.minted_amount),
             dao_id.eq(param_token_info.dao_id),
             only_creator_can_mint.eq(param_token_info.only_creator_can_mint),
+            updated_at_height.eq(param_token_info.updated_at_height),
+            updated_by_transition_id.eq(param_token_info.updated_by_transition_id),
+            updated_at_timestamp.eq(param_token_info.updated_at_timestamp),
         ))
         .execute(conn)
         .expect("Update: Error");
@@ -538,6 +1651,9 @@ pub fn insert_balances(
         owner: &param_balances.owner,
         amount: param_balances.amount,
         token_info_id: param_balances.token_info_id,
+        updated_at_height: param_balances.updated_at_height,
+        updated_by_transition_id: param_balances.updated_by_transition_id.as_deref(),
+        updated_at_timestamp: param_balances.updated_at_timestamp,
     };
 
     diesel::insert_into(balances::table)
@@ -580,6 +1696,9 @@ pub fn update_balances(
             owner.eq(param_balances.owner),
             amount.eq(param_balances.amount),
             token_info_id.eq(param_balances.token_info_id),
+            updated_at_height.eq(param_balances.updated_at_height),
+            updated_by_transition_id.eq(param_balances.updated_by_transition_id),
+            updated_at_timestamp.eq(param_balances.updated_at_timestamp),
         ))
         .execute(conn)
         .expect("Update: Error");
@@ -598,6 +1717,9 @@ pub fn insert_stake_amounts(
         owner: &param_stake_amounts.owner,
         amount: param_stake_amounts.amount,
         token_info_id: param_stake_amounts.token_info_id,
+        updated_at_height: param_stake_amounts.updated_at_height,
+        updated_by_transition_id: param_stake_amounts.updated_by_transition_id.as_deref(),
+        updated_at_timestamp: param_stake_amounts.updated_at_timestamp,
     };
 
     diesel::insert_into(stake_amounts::table)
@@ -640,6 +1762,9 @@ pub fn update_stake_amounts(
             owner.eq(param_stake_amounts.owner),
             amount.eq(param_stake_amounts.amount),
             token_info_id.eq(param_stake_amounts.token_info_id),
+            updated_at_height.eq(param_stake_amounts.updated_at_height),
+            updated_by_transition_id.eq(param_stake_amounts.updated_by_transition_id),
+            updated_at_timestamp.eq(param_stake_amounts.updated_at_timestamp),
         ))
         .execute(conn)
         .expect("Update: Error");
@@ -658,6 +1783,9 @@ pub fn insert_profile(
         name: &param_profile.name,
         avatar: &param_profile.avatar,
         bio: &param_profile.bio,
+        updated_at_height: param_profile.updated_at_height,
+        updated_by_transition_id: param_profile.updated_by_transition_id.as_deref(),
+        updated_at_timestamp: param_profile.updated_at_timestamp,
     };
 
     diesel::insert_into(profiles::table)
@@ -699,6 +1827,9 @@ pub fn update_profile(
             name.eq(param_profile.name),
             avatar.eq(param_profile.avatar),
             bio.eq(param_profile.bio),
+            updated_at_height.eq(param_profile.updated_at_height),
+            updated_by_transition_id.eq(param_profile.updated_by_transition_id),
+            updated_at_timestamp.eq(param_profile.updated_at_timestamp),
         ))
         .execute(conn)
         .expect("Update: Error");
@@ -726,6 +1857,9 @@ pub fn create_dao(
         vote_count: param_dao.vote_count,
         passed_votes_proportion: param_dao.passed_votes_proportion,
         passed_tokens_proportion: param_dao.passed_tokens_proportion,
+        updated_at_height: param_dao.updated_at_height,
+        updated_by_transition_id: param_dao.updated_by_transition_id.as_deref(),
+        updated_at_timestamp: param_dao.updated_at_timestamp,
     };
 
     diesel::insert_into(daos::table)
@@ -777,6 +1911,9 @@ pub fn update_dao(
             vote_count.eq(param_dao.vote_count),
             passed_votes_proportion.eq(param_dao.passed_votes_proportion),
             passed_tokens_proportion.eq(param_dao.passed_tokens_proportion),
+            updated_at_height.eq(param_dao.updated_at_height),
+            updated_by_transition_id.eq(param_dao.updated_by_transition_id),
+            updated_at_timestamp.eq(param_dao.updated_at_timestamp),
         ))
         .execute(conn)
         .expect("Update: Error");
@@ -803,6 +1940,9 @@ pub fn create_proposal(
         adopt: param_proposal.adopt,
         reject: param_proposal.reject,
         status: param_proposal.status,
+        updated_at_height: param_proposal.updated_at_height,
+        updated_by_transition_id: param_proposal.updated_by_transition_id.as_deref(),
+        updated_at_timestamp: param_proposal.updated_at_timestamp,
     };
 
     diesel::insert_into(proposals::table)
@@ -854,6 +1994,9 @@ pub fn update_proposal(
             adopt.eq(param_proposal.adopt),
             reject.eq(param_proposal.reject),
             status.eq(param_proposal.status),
+            updated_at_height.eq(param_proposal.updated_at_height),
+            updated_by_transition_id.eq(param_proposal.updated_by_transition_id),
+            updated_at_timestamp.eq(param_proposal.updated_at_timestamp),
         ))
         .execute(conn)
         .expect("Update: Error");
@@ -982,6 +2125,9 @@ pub fn insert_votes(
         is_agreed: param_vote.is_agreed,
         time: param_vote.time,
         amount: param_vote.amount,
+        updated_at_height: param_vote.updated_at_height,
+        updated_by_transition_id: param_vote.updated_by_transition_id.as_deref(),
+        updated_at_timestamp: param_vote.updated_at_timestamp,
     };
 
     diesel::insert_into(votes::table)
@@ -0,0 +1,303 @@
+use crate::amount::Amount;
+use anyhow::{anyhow, Error};
+use async_graphql::SimpleObject;
+use diesel::{r2d2::ConnectionManager, sql_types::Text, PgConnection, RunQueryDsl};
+use futures03::{Stream, StreamExt};
+use lazy_static::lazy_static;
+use r2d2::PooledConnection;
+use r2d2_postgres::postgres::{fallible_iterator::FallibleIterator, Client, NoTls};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::HashMap, env};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// Postgres channel used to forward events from a `sync` process to `serve` processes.
+pub const NOTIFY_CHANNEL: &str = "indexer_events";
+
+const EVENTS_CAPACITY: usize = 1024;
+
+lazy_static! {
+    pub static ref EVENTS: broadcast::Sender<IndexerEvent> = broadcast::channel(EVENTS_CAPACITY).0;
+}
+
+thread_local! {
+    /// Events published in the transactions open on this thread, one list
+    /// per nesting level, see `hold_events`.
+    static PENDING_EVENTS: RefCell<Vec<Vec<IndexerEvent>>> = RefCell::new(Vec::new());
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject, ToSchema)]
+pub struct BlockIndexed {
+    pub height: i64,
+    pub block_hash: String,
+    pub timestamp: i64,
+    pub record_count: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject, ToSchema)]
+pub struct ProposalStatusChanged {
+    pub dao_id: i64,
+    pub proposal_id: i64,
+    pub status: i64,
+    pub adopt: Amount,
+    pub reject: Amount,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject, ToSchema)]
+pub struct VoteCast {
+    pub key: String,
+    pub dao_id: i64,
+    pub proposal_id: i64,
+    pub voter: String,
+    pub is_agreed: bool,
+    pub time: i64,
+    pub amount: Amount,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, SimpleObject, ToSchema)]
+pub struct BalanceChanged {
+    pub owner: String,
+    pub token_info_id: i64,
+    pub amount: Amount,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "topic", rename_all = "snake_case")]
+pub enum IndexerEvent {
+    Block(BlockIndexed),
+    ProposalStatus(ProposalStatusChanged),
+    Vote(VoteCast),
+    Balance(BalanceChanged),
+}
+
+impl IndexerEvent {
+    pub fn topic_name(&self) -> &'static str {
+        match self {
+            IndexerEvent::Block(_) => "block",
+            IndexerEvent::ProposalStatus(_) => "proposal_status",
+            IndexerEvent::Vote(_) => "vote",
+            IndexerEvent::Balance(_) => "balance",
+        }
+    }
+}
+
+/// What a client is subscribed to. Optional fields narrow the topic down,
+/// `None` matches every event of that topic.
+#[derive(Clone, Debug)]
+pub enum Topic {
+    Blocks,
+    ProposalStatus { dao_id: Option<i64> },
+    Votes { proposal_id: Option<i64> },
+    Balances { address: Option<String> },
+}
+
+impl Topic {
+    pub fn matches(&self, event: &IndexerEvent) -> bool {
+        match (self, event) {
+            (Topic::Blocks, IndexerEvent::Block(_)) => true,
+            (Topic::ProposalStatus { dao_id }, IndexerEvent::ProposalStatus(event)) => {
+                dao_id.map_or(true, |id| id == event.dao_id)
+            }
+            (Topic::Votes { proposal_id }, IndexerEvent::Vote(event)) => {
+                proposal_id.map_or(true, |id| id == event.proposal_id)
+            }
+            (Topic::Balances { address }, IndexerEvent::Balance(event)) => address
+                .as_ref()
+                .map_or(true, |address| *address == event.owner),
+            _ => false,
+        }
+    }
+
+    /// Builds a topic from query parameters, e.g. `?topic=votes&proposal_id=1`.
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Self, Error> {
+        let parse_id = |name: &str| -> Result<Option<i64>, Error> {
+            match params.get(name) {
+                Some(value) => Ok(Some(value.parse::<i64>()?)),
+                None => Ok(None),
+            }
+        };
+
+        match params.get("topic").map(String::as_str) {
+            Some("blocks") => Ok(Topic::Blocks),
+            Some("proposal_status") => Ok(Topic::ProposalStatus {
+                dao_id: parse_id("dao_id")?,
+            }),
+            Some("votes") => Ok(Topic::Votes {
+                proposal_id: parse_id("proposal_id")?,
+            }),
+            Some("balances") => Ok(Topic::Balances {
+                address: params.get("address").cloned(),
+            }),
+            Some(topic) => Err(anyhow!("unknown topic {}", topic)),
+            None => Err(anyhow!("missing topic")),
+        }
+    }
+}
+
+/// Streams the events of a topic published from now on. Events missed because
+/// the subscriber lagged behind are skipped.
+pub fn subscribe(topic: Topic) -> impl Stream<Item = IndexerEvent> {
+    BroadcastStream::new(EVENTS.subscribe()).filter_map(move |event| {
+        let event = event.ok().filter(|event| topic.matches(event));
+        async move { event }
+    })
+}
+
+/// Runs the transaction `f`, holding back the events queued with
+/// `queue_event` meanwhile. Returns them once `f` committed, unless it is
+/// nested in another transaction, which takes them over. The events of a
+/// transaction that fails are dropped.
+pub fn hold_events<F>(f: F) -> Result<Vec<IndexerEvent>, Error>
+where
+    F: FnOnce() -> Result<(), Error>,
+{
+    // Pops the level even when `f` panics, so that later events of this
+    // thread aren't held back for ever.
+    struct Level;
+    impl Drop for Level {
+        fn drop(&mut self) {
+            PENDING_EVENTS.with(|pending| pending.borrow_mut().pop());
+        }
+    }
+
+    PENDING_EVENTS.with(|pending| pending.borrow_mut().push(Vec::new()));
+    let level = Level;
+    let result = f();
+    let events = PENDING_EVENTS.with(|pending| {
+        pending
+            .borrow_mut()
+            .last_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    });
+    drop(level);
+    result?;
+
+    Ok(
+        PENDING_EVENTS.with(|pending| match pending.borrow_mut().last_mut() {
+            Some(outer) => {
+                outer.extend(events);
+                Vec::new()
+            }
+            None => events,
+        }),
+    )
+}
+
+/// Queues `event` until the transaction open on this thread commits, see
+/// `hold_events`. Gives it back when no transaction is open.
+pub fn queue_event(event: IndexerEvent) -> Option<IndexerEvent> {
+    PENDING_EVENTS.with(|pending| match pending.borrow_mut().last_mut() {
+        Some(events) => {
+            events.push(event);
+            None
+        }
+        None => Some(event),
+    })
+}
+
+/// Publishes an event to in-process subscribers and, through `pg_notify`, to
+/// `serve` processes running separately from `sync`.
+pub fn publish_event(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    event: IndexerEvent,
+) {
+    if let Err(err) = notify_event(conn, &event) {
+        warn!("Notify event error {:#}", err);
+    }
+
+    // Sending only fails when nobody is subscribed, which is fine.
+    let _ = EVENTS.send(event);
+}
+
+fn notify_event(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    event: &IndexerEvent,
+) -> Result<(), Error> {
+    let payload = serde_json::to_string(event)?;
+
+    diesel::sql_query("SELECT pg_notify($1, $2)")
+        .bind::<Text, _>(NOTIFY_CHANNEL)
+        .bind::<Text, _>(payload)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Forwards events published by a separate `sync` process to the in-process
+/// channel. Blocks forever, so it must run on a dedicated thread.
+pub fn listen_notifications() -> Result<(), Error> {
+    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let mut client = Client::connect(&db_url, NoTls)?;
+    client.batch_execute(&format!("LISTEN {}", NOTIFY_CHANNEL))?;
+    info!("Listening for events on channel {}", NOTIFY_CHANNEL);
+
+    let mut notifications = client.notifications();
+    let mut iter = notifications.blocking_iter();
+    while let Some(notification) = iter.next()? {
+        match serde_json::from_str::<IndexerEvent>(notification.payload()) {
+            Ok(event) => {
+                let _ = EVENTS.send(event);
+            }
+            Err(err) => warn!("Invalid event payload {:#}", err),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(height: i64) -> IndexerEvent {
+        IndexerEvent::Block(BlockIndexed {
+            height,
+            block_hash: format!("hash-{}", height),
+            timestamp: 0,
+            record_count: 0,
+        })
+    }
+
+    fn heights(events: &[IndexerEvent]) -> Vec<i64> {
+        events
+            .iter()
+            .map(|event| match event {
+                IndexerEvent::Block(block) => block.height,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn holds_events_until_the_outer_transaction_commits() {
+        assert!(queue_event(block(0)).is_some());
+
+        let events = hold_events(|| {
+            assert!(queue_event(block(1)).is_none());
+            let nested = hold_events(|| {
+                queue_event(block(2));
+                Ok(())
+            })
+            .unwrap();
+            assert!(nested.is_empty());
+            let rolled_back = hold_events(|| {
+                queue_event(block(3));
+                Err(anyhow!("rollback"))
+            });
+            assert!(rolled_back.is_err());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(heights(&events), vec![1, 2]);
+
+        let rolled_back = hold_events(|| {
+            queue_event(block(4));
+            Err(anyhow!("rollback"))
+        });
+        assert!(rolled_back.is_err());
+        assert!(queue_event(block(5)).is_some());
+    }
+}
@@ -0,0 +1,381 @@
+use crate::storage::StorageConfig;
+use anyhow::{anyhow, Error};
+use clap::ValueEnum;
+use parquet::{
+    basic::Compression,
+    data_type::{BoolType, ByteArray, ByteArrayType, FixedLenByteArrayType, Int64Type},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::parser::parse_message_type,
+};
+use r2d2_postgres::postgres::{types::Type, Client, NoTls, Row};
+use std::{env, fs, fs::File, path::PathBuf, sync::Arc};
+use tracing::info;
+
+/// Bytes of a `DECIMAL(20,0)` Parquet column, enough for any `u64`.
+const AMOUNT_BYTES: usize = 9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Parquet,
+    Csv,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+    pub out_dir: PathBuf,
+    pub format: ExportFormat,
+    /// Number of blocks per file.
+    pub partition_size: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ColumnKind {
+    Int,
+    Bool,
+    Text,
+    /// `NUMERIC` amounts, selected as text.
+    Amount,
+    /// `JSON`/`JSONB`, selected as text.
+    Json,
+}
+
+struct Column {
+    name: String,
+    kind: ColumnKind,
+}
+
+/// Exports never go through the `Storage` abstraction: they need the column
+/// types of arbitrary tables, which only the Postgres client exposes.
+fn connect() -> Result<Client, Error> {
+    if let StorageConfig::Sqlite(_) = StorageConfig::from_env() {
+        return Err(anyhow!("Exporting needs Postgres"));
+    }
+    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    Ok(Client::connect(&db_url, NoTls)?)
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn table_columns(client: &mut Client, table: &str) -> Result<Vec<Column>, Error> {
+    let statement = client.prepare(&format!("SELECT * FROM {} LIMIT 0", quote_ident(table)))?;
+
+    let columns = statement
+        .columns()
+        .iter()
+        .map(|column| {
+            let kind = match *column.type_() {
+                Type::INT2 | Type::INT4 | Type::INT8 => ColumnKind::Int,
+                Type::BOOL => ColumnKind::Bool,
+                Type::NUMERIC => ColumnKind::Amount,
+                Type::JSON | Type::JSONB => ColumnKind::Json,
+                _ => ColumnKind::Text,
+            };
+            Column {
+                name: column.name().to_string(),
+                kind,
+            }
+        })
+        .collect();
+
+    Ok(columns)
+}
+
+/// The block height a row belongs to: its own for raw tables, the one of
+/// its last update for domain tables.
+fn partition_column(columns: &[Column]) -> Option<&str> {
+    ["height", "updated_at_height"]
+        .into_iter()
+        .find(|name| columns.iter().any(|column| column.name == *name))
+}
+
+fn select_rows(
+    client: &mut Client,
+    table: &str,
+    columns: &[Column],
+    filter: &str,
+    params: &[i64],
+) -> Result<Vec<Row>, Error> {
+    let select_list = columns
+        .iter()
+        .map(|column| match column.kind {
+            ColumnKind::Int => format!("{}::bigint", quote_ident(&column.name)),
+            ColumnKind::Bool => quote_ident(&column.name),
+            _ => format!("{}::text", quote_ident(&column.name)),
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    let query = format!(
+        "SELECT {} FROM {} {}",
+        select_list,
+        quote_ident(table),
+        filter
+    );
+
+    let params = params
+        .iter()
+        .map(|param| param as &(dyn r2d2_postgres::postgres::types::ToSql + Sync))
+        .collect::<Vec<_>>();
+    Ok(client.query(&query, &params)?)
+}
+
+/// Writes the rows of `table` with a height in `[start_block, end_block]`
+/// to one file per `partition_size` blocks, named
+/// `<out_dir>/<table>/<table>_<first height>_<last height>.<format>`. Tables
+/// without a height are written whole to `<table>.<format>`, and so are rows
+/// without one (written before provenance was recorded) to
+/// `<table>_unknown.<format>` when the range isn't restricted. Returns the
+/// files written.
+pub fn export_table(
+    table: &str,
+    start_block: i64,
+    end_block: i64,
+    options: &ExportOptions,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut client = connect()?;
+    let columns = table_columns(&mut client, table)?;
+    let table_dir = options.out_dir.join(table);
+    fs::create_dir_all(&table_dir)?;
+
+    let mut written = Vec::new();
+    let mut write = |name: String, rows: Vec<Row>| -> Result<(), Error> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let path = table_dir.join(format!("{}.{}", name, options.format.extension()));
+        match options.format {
+            ExportFormat::Parquet => write_parquet(&path, table, &columns, &rows)?,
+            ExportFormat::Csv => write_csv(&path, &columns, &rows)?,
+        }
+        info!("Exported {} rows to {}", rows.len(), path.display());
+        written.push(path);
+        Ok(())
+    };
+
+    let Some(height) = partition_column(&columns).map(quote_ident) else {
+        let rows = select_rows(&mut client, table, &columns, "", &[])?;
+        write(table.to_string(), rows)?;
+        return Ok(written);
+    };
+
+    let bounds = client.query_one(
+        &format!(
+            "SELECT MIN({0}), MAX({0}) FROM {1} WHERE {0} BETWEEN $1 AND $2",
+            height,
+            quote_ident(table)
+        ),
+        &[&start_block, &end_block],
+    )?;
+    let (min, max): (Option<i64>, Option<i64>) = (bounds.get(0), bounds.get(1));
+    if let (Some(min), Some(max)) = (min, max) {
+        let mut partition_start = min - min.rem_euclid(options.partition_size);
+        while partition_start <= max {
+            let first = partition_start.max(start_block);
+            let last = (partition_start + options.partition_size - 1).min(end_block);
+            let rows = select_rows(
+                &mut client,
+                table,
+                &columns,
+                &format!("WHERE {0} BETWEEN $1 AND $2 ORDER BY {0}", height),
+                &[first, last],
+            )?;
+            write(format!("{}_{}_{}", table, first, last), rows)?;
+            partition_start += options.partition_size;
+        }
+    }
+
+    if start_block <= 0 && end_block == i64::MAX {
+        let rows = select_rows(
+            &mut client,
+            table,
+            &columns,
+            &format!("WHERE {} IS NULL", height),
+            &[],
+        )?;
+        write(format!("{}_unknown", table), rows)?;
+    }
+
+    Ok(written)
+}
+
+fn write_csv(path: &PathBuf, columns: &[Column], rows: &[Row]) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(columns.iter().map(|column| column.name.as_str()))?;
+
+    for row in rows {
+        let record = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| match column.kind {
+                ColumnKind::Int => row.get::<_, Option<i64>>(i).map(|v| v.to_string()),
+                ColumnKind::Bool => row.get::<_, Option<bool>>(i).map(|v| v.to_string()),
+                _ => row.get::<_, Option<String>>(i),
+            })
+            .map(Option::unwrap_or_default)
+            .collect::<Vec<String>>();
+        writer.write_record(&record)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn parquet_schema(table: &str, columns: &[Column]) -> String {
+    let fields = columns
+        .iter()
+        .map(|column| {
+            let field = match column.kind {
+                ColumnKind::Int => "int64".to_string(),
+                ColumnKind::Bool => "boolean".to_string(),
+                ColumnKind::Text => "binary".to_string(),
+                ColumnKind::Amount => format!("fixed_len_byte_array({})", AMOUNT_BYTES),
+                ColumnKind::Json => "binary".to_string(),
+            };
+            let annotation = match column.kind {
+                ColumnKind::Text => " (UTF8)",
+                ColumnKind::Amount => " (DECIMAL(20,0))",
+                ColumnKind::Json => " (JSON)",
+                _ => "",
+            };
+            format!("optional {} {}{};", field, column.name, annotation)
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    format!("message {} {{ {} }}", table, fields)
+}
+
+/// Big-endian two's complement, as Parquet expects decimals.
+fn amount_bytes(value: &str) -> Result<ByteArray, Error> {
+    let value: i128 = value.parse()?;
+    Ok(ByteArray::from(
+        value.to_be_bytes()[16 - AMOUNT_BYTES..].to_vec(),
+    ))
+}
+
+fn write_parquet(
+    path: &PathBuf,
+    table: &str,
+    columns: &[Column],
+    rows: &[Row],
+) -> Result<(), Error> {
+    let schema = Arc::new(parse_message_type(&parquet_schema(table, columns))?);
+    let properties = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+    let mut writer = SerializedFileWriter::new(File::create(path)?, schema, properties)?;
+    let mut row_group = writer.next_row_group()?;
+
+    for (i, column) in columns.iter().enumerate() {
+        let mut column_writer = row_group
+            .next_column()?
+            .ok_or_else(|| anyhow!("missing Parquet column {}", column.name))?;
+        let definition_levels = |present: &dyn Fn(&Row) -> bool| {
+            rows.iter()
+                .map(|row| present(row) as i16)
+                .collect::<Vec<i16>>()
+        };
+
+        match column.kind {
+            ColumnKind::Int => {
+                let values = rows.iter().map(|row| row.get::<_, Option<i64>>(i));
+                let levels = definition_levels(&|row| row.get::<_, Option<i64>>(i).is_some());
+                column_writer.typed::<Int64Type>().write_batch(
+                    &values.flatten().collect::<Vec<i64>>(),
+                    Some(&levels),
+                    None,
+                )?;
+            }
+            ColumnKind::Bool => {
+                let values = rows.iter().map(|row| row.get::<_, Option<bool>>(i));
+                let levels = definition_levels(&|row| row.get::<_, Option<bool>>(i).is_some());
+                column_writer.typed::<BoolType>().write_batch(
+                    &values.flatten().collect::<Vec<bool>>(),
+                    Some(&levels),
+                    None,
+                )?;
+            }
+            ColumnKind::Text | ColumnKind::Json => {
+                let values = rows
+                    .iter()
+                    .filter_map(|row| row.get::<_, Option<String>>(i))
+                    .map(|value| ByteArray::from(value.into_bytes()))
+                    .collect::<Vec<ByteArray>>();
+                let levels = definition_levels(&|row| row.get::<_, Option<&str>>(i).is_some());
+                column_writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+            ColumnKind::Amount => {
+                let values = rows
+                    .iter()
+                    .filter_map(|row| row.get::<_, Option<&str>>(i))
+                    .map(|value| amount_bytes(value).map(Into::into))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let levels = definition_levels(&|row| row.get::<_, Option<&str>>(i).is_some());
+                column_writer.typed::<FixedLenByteArrayType>().write_batch(
+                    &values,
+                    Some(&levels),
+                    None,
+                )?;
+            }
+        }
+        column_writer.close()?;
+    }
+
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Exports each block range of `tables` once sync has moved past it, so the
+/// files of a range are only written when complete. The range being synced
+/// when the stream ends is left to the `export` command.
+pub struct ExportSink {
+    tables: Vec<String>,
+    options: ExportOptions,
+    /// First height of the range not exported yet.
+    next_start: Option<i64>,
+}
+
+impl ExportSink {
+    pub fn new(tables: Vec<String>, options: ExportOptions) -> Self {
+        ExportSink {
+            tables,
+            options,
+            next_start: None,
+        }
+    }
+
+    fn partition_start(&self, height: i64) -> i64 {
+        height - height.rem_euclid(self.options.partition_size)
+    }
+
+    /// Called after a block is written. Failed exports are retried on the
+    /// next block.
+    pub fn block_indexed(&mut self, height: i64) -> Result<(), Error> {
+        let partition_start = self.partition_start(height);
+        let next_start = *self.next_start.get_or_insert(partition_start);
+        if partition_start <= next_start {
+            return Ok(());
+        }
+
+        for table in self.tables.iter() {
+            export_table(table, next_start, partition_start - 1, &self.options)?;
+        }
+        self.next_start = Some(partition_start);
+        Ok(())
+    }
+}
@@ -0,0 +1,550 @@
+use crate::{
+    database::{
+        get_balances_by_owner, get_block_by_height, get_daos_by_creators, get_daos_by_ids,
+        get_daos_page, get_latest_block, get_profiles_by_addresses, get_proposals_by_ids,
+        get_proposals_page, get_proposals_pages_by_dao_ids, get_records_page, get_stakes_by_owner,
+        get_token_infos_by_ids, get_token_infos_page, get_transactions_by_height,
+        get_vote_by_voter, get_vote_totals_by_proposal_id, get_votes_pages_by_proposal_ids,
+        with_conn,
+    },
+    events::{
+        subscribe, BalanceChanged, BlockIndexed, IndexerEvent, ProposalStatusChanged, Topic,
+        VoteCast,
+    },
+    models::{
+        Balances, Blocks, Daos, Input, Output, Profiles, Proposals, Record, RecordFilter,
+        StakeAmounts, TokenInfos, Transactions, VoteTotals, Votes,
+    },
+    pagination::page,
+    program_handler::bhp256_hash_address,
+};
+use async_graphql::{
+    async_trait,
+    dataloader::{DataLoader, Loader},
+    http::{playground_source, GraphQLPlaygroundConfig},
+    ComplexObject, Context, EmptyMutation, Object, Result, Schema, Subscription,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::{response::Html, Extension};
+use futures03::{Stream, StreamExt};
+use std::{collections::HashMap, sync::Arc};
+
+pub type IndexerSchema = Schema<QueryRoot, EmptyMutation, SubscriptionRoot>;
+
+/// Deepest selection a query may nest; enough for the playground's
+/// introspection query.
+const MAX_QUERY_DEPTH: usize = 16;
+/// Most fields a query may resolve, counting each item of a list page.
+const MAX_QUERY_COMPLEXITY: usize = 50_000;
+
+pub fn build_schema() -> IndexerSchema {
+    Schema::build(QueryRoot, EmptyMutation, SubscriptionRoot)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .data(DataLoader::new(DaoLoader, tokio::spawn))
+        .data(DataLoader::new(DaoProposalsLoader, tokio::spawn))
+        .data(DataLoader::new(ProposalLoader, tokio::spawn))
+        .data(DataLoader::new(ProposalVotesLoader, tokio::spawn))
+        .data(DataLoader::new(TokenInfoLoader, tokio::spawn))
+        .data(DataLoader::new(ProfileLoader, tokio::spawn))
+        .data(DataLoader::new(CreatorDaosLoader, tokio::spawn))
+        .finish()
+}
+
+/// Complexity of a paginated list field: its selection once per item of the
+/// largest page it can return.
+fn page_complexity(limit: Option<i64>, child_complexity: usize) -> usize {
+    let (_, limit) = page(None, limit);
+    (limit as usize).max(1) * child_complexity
+}
+
+pub async fn graphql_handler(
+    Extension(schema): Extension<IndexerSchema>,
+    req: GraphQLRequest,
+) -> GraphQLResponse {
+    schema.execute(req.into_inner()).await.into()
+}
+
+pub async fn graphql_playground_handler() -> Html<String> {
+    Html(playground_source(
+        GraphQLPlaygroundConfig::new("/graphql").subscription_endpoint("/graphql/ws"),
+    ))
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn daos(&self, offset: Option<i64>, limit: Option<i64>) -> Result<Vec<Daos>> {
+        let (offset, limit) = page(offset, limit);
+        Ok(with_conn(move |conn| get_daos_page(conn, offset, limit)).await?)
+    }
+
+    async fn dao(&self, id: i64) -> Result<Option<Daos>> {
+        Ok(with_conn(move |conn| get_daos_by_ids(conn, &[id]))
+            .await?
+            .pop())
+    }
+
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn proposals(&self, offset: Option<i64>, limit: Option<i64>) -> Result<Vec<Proposals>> {
+        let (offset, limit) = page(offset, limit);
+        Ok(with_conn(move |conn| get_proposals_page(conn, offset, limit)).await?)
+    }
+
+    async fn proposal(&self, id: i64) -> Result<Option<Proposals>> {
+        Ok(with_conn(move |conn| get_proposals_by_ids(conn, &[id]))
+            .await?
+            .pop())
+    }
+
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn token_infos(
+        &self,
+        offset: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<Vec<TokenInfos>> {
+        let (offset, limit) = page(offset, limit);
+        Ok(with_conn(move |conn| get_token_infos_page(conn, offset, limit)).await?)
+    }
+
+    async fn token_info(&self, id: i64) -> Result<Option<TokenInfos>> {
+        Ok(with_conn(move |conn| get_token_infos_by_ids(conn, &[id]))
+            .await?
+            .pop())
+    }
+
+    async fn profile(&self, address: String) -> Result<Option<Profiles>> {
+        Ok(
+            with_conn(move |conn| get_profiles_by_addresses(conn, &[address]))
+                .await?
+                .pop(),
+        )
+    }
+
+    /// Balances held by an Aleo address.
+    async fn balances(&self, address: String) -> Result<Vec<Balances>> {
+        let hash_addr = bhp256_hash_address(&address)?;
+        Ok(with_conn(move |conn| get_balances_by_owner(conn, hash_addr.to_string())).await?)
+    }
+
+    /// Stakes held by an Aleo address.
+    async fn stakes(&self, address: String) -> Result<Vec<StakeAmounts>> {
+        let hash_addr = bhp256_hash_address(&address)?;
+        Ok(with_conn(move |conn| get_stakes_by_owner(conn, hash_addr.to_string())).await?)
+    }
+
+    /// The block indexed at a height, or the latest one when omitted.
+    async fn block(&self, height: Option<i64>) -> Result<Option<Blocks>> {
+        Ok(with_conn(move |conn| match height {
+            Some(height) => get_block_by_height(conn, height),
+            None => get_latest_block(conn),
+        })
+        .await?)
+    }
+
+    /// Records in the block range, optionally only the transitions with an
+    /// input or output matching the given id, value and type.
+    #[allow(clippy::too_many_arguments)]
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn records(
+        &self,
+        start_block: Option<i64>,
+        end_block: Option<i64>,
+        id: Option<String>,
+        value: Option<String>,
+        #[graphql(name = "type")] r#type: Option<String>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<Vec<Record>> {
+        let (offset, limit) = page(offset, limit);
+        let start_block = start_block.unwrap_or(0);
+        let end_block = end_block.unwrap_or(i64::MAX);
+        let filter = RecordFilter { id, value, r#type };
+        Ok(with_conn(move |conn| {
+            get_records_page(conn, start_block, end_block, &filter, offset, limit)
+        })
+        .await?)
+    }
+}
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Every block once it has been indexed.
+    async fn blocks(&self) -> impl Stream<Item = BlockIndexed> {
+        subscribe(Topic::Blocks).filter_map(|event| async move {
+            match event {
+                IndexerEvent::Block(block) => Some(block),
+                _ => None,
+            }
+        })
+    }
+
+    /// Proposals whose status changed, optionally restricted to one DAO.
+    async fn proposal_status(
+        &self,
+        dao_id: Option<i64>,
+    ) -> impl Stream<Item = ProposalStatusChanged> {
+        subscribe(Topic::ProposalStatus { dao_id }).filter_map(|event| async move {
+            match event {
+                IndexerEvent::ProposalStatus(proposal) => Some(proposal),
+                _ => None,
+            }
+        })
+    }
+
+    /// Votes cast, optionally restricted to one proposal.
+    async fn votes(&self, proposal_id: Option<i64>) -> impl Stream<Item = VoteCast> {
+        subscribe(Topic::Votes { proposal_id }).filter_map(|event| async move {
+            match event {
+                IndexerEvent::Vote(vote) => Some(vote),
+                _ => None,
+            }
+        })
+    }
+
+    /// Balance changes, optionally restricted to one address.
+    async fn balances(&self, address: Option<String>) -> impl Stream<Item = BalanceChanged> {
+        subscribe(Topic::Balances { address }).filter_map(|event| async move {
+            match event {
+                IndexerEvent::Balance(balance) => Some(balance),
+                _ => None,
+            }
+        })
+    }
+}
+
+#[ComplexObject]
+impl Daos {
+    async fn token_info(&self, ctx: &Context<'_>) -> Result<Option<TokenInfos>> {
+        let loader = ctx.data_unchecked::<DataLoader<TokenInfoLoader>>();
+        Ok(loader.load_one(self.token_info_id).await?)
+    }
+
+    async fn creator_profile(&self, ctx: &Context<'_>) -> Result<Option<Profiles>> {
+        let loader = ctx.data_unchecked::<DataLoader<ProfileLoader>>();
+        Ok(loader.load_one(self.creator.clone()).await?)
+    }
+
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn proposals(
+        &self,
+        ctx: &Context<'_>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<Vec<Proposals>> {
+        let loader = ctx.data_unchecked::<DataLoader<DaoProposalsLoader>>();
+        let (offset, limit) = page(offset, limit);
+        Ok(loader
+            .load_one((self.id, offset, limit))
+            .await?
+            .unwrap_or_default())
+    }
+}
+
+#[ComplexObject]
+impl Proposals {
+    async fn dao(&self, ctx: &Context<'_>) -> Result<Option<Daos>> {
+        let loader = ctx.data_unchecked::<DataLoader<DaoLoader>>();
+        Ok(loader.load_one(self.dao_id).await?)
+    }
+
+    async fn proposer_profile(&self, ctx: &Context<'_>) -> Result<Option<Profiles>> {
+        let loader = ctx.data_unchecked::<DataLoader<ProfileLoader>>();
+        Ok(loader.load_one(self.proposer.clone()).await?)
+    }
+
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn votes(
+        &self,
+        ctx: &Context<'_>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+    ) -> Result<Vec<Votes>> {
+        let loader = ctx.data_unchecked::<DataLoader<ProposalVotesLoader>>();
+        let (offset, limit) = page(offset, limit);
+        Ok(loader
+            .load_one((self.id, offset, limit))
+            .await?
+            .unwrap_or_default())
+    }
+
+    async fn vote_totals(&self) -> Result<VoteTotals> {
+        let id = self.id;
+        Ok(with_conn(move |conn| get_vote_totals_by_proposal_id(conn, id)).await?)
+    }
+}
+
+#[ComplexObject]
+impl Votes {
+    async fn proposal(&self, ctx: &Context<'_>) -> Result<Option<Proposals>> {
+        let loader = ctx.data_unchecked::<DataLoader<ProposalLoader>>();
+        Ok(loader.load_one(self.proposal_id).await?)
+    }
+
+    async fn voter_profile(&self, ctx: &Context<'_>) -> Result<Option<Profiles>> {
+        let loader = ctx.data_unchecked::<DataLoader<ProfileLoader>>();
+        Ok(loader.load_one(self.voter.clone()).await?)
+    }
+}
+
+#[ComplexObject]
+impl TokenInfos {
+    async fn dao(&self, ctx: &Context<'_>) -> Result<Option<Daos>> {
+        let loader = ctx.data_unchecked::<DataLoader<DaoLoader>>();
+        Ok(loader.load_one(self.dao_id).await?)
+    }
+}
+
+#[ComplexObject]
+impl Balances {
+    async fn token_info(&self, ctx: &Context<'_>) -> Result<Option<TokenInfos>> {
+        let loader = ctx.data_unchecked::<DataLoader<TokenInfoLoader>>();
+        Ok(loader.load_one(self.token_info_id).await?)
+    }
+}
+
+#[ComplexObject]
+impl StakeAmounts {
+    async fn token_info(&self, ctx: &Context<'_>) -> Result<Option<TokenInfos>> {
+        let loader = ctx.data_unchecked::<DataLoader<TokenInfoLoader>>();
+        Ok(loader.load_one(self.token_info_id).await?)
+    }
+}
+
+#[ComplexObject]
+impl Profiles {
+    async fn created_daos(&self, ctx: &Context<'_>) -> Result<Vec<Daos>> {
+        let loader = ctx.data_unchecked::<DataLoader<CreatorDaosLoader>>();
+        Ok(loader
+            .load_one(self.address.clone())
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Votes cast by this address, most recent first.
+    #[graphql(complexity = "page_complexity(limit, child_complexity)")]
+    async fn votes(&self, offset: Option<i64>, limit: Option<i64>) -> Result<Vec<Votes>> {
+        let (offset, limit) = page(offset, limit);
+        let address = self.address.clone();
+        Ok(with_conn(move |conn| get_vote_by_voter(conn, address, offset, limit)).await?)
+    }
+}
+
+#[ComplexObject]
+impl Blocks {
+    async fn transactions(&self) -> Result<Vec<Transactions>> {
+        let height = self.height;
+        Ok(with_conn(move |conn| get_transactions_by_height(conn, height)).await?)
+    }
+}
+
+#[ComplexObject]
+impl Record {
+    async fn inputs(&self) -> Result<Vec<Input>> {
+        Ok(serde_json::from_value(self.inputs.clone())?)
+    }
+
+    async fn outputs(&self) -> Result<Vec<Output>> {
+        Ok(serde_json::from_value(self.outputs.clone())?)
+    }
+}
+
+pub struct DaoLoader;
+
+#[async_trait::async_trait]
+impl Loader<i64> for DaoLoader {
+    type Value = Daos;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, Self::Value>, Self::Error> {
+        let keys = keys.to_vec();
+        let daos = with_conn(move |conn| get_daos_by_ids(conn, &keys))
+            .await
+            .map_err(Arc::new)?;
+        Ok(daos.into_iter().map(|dao| (dao.id, dao)).collect())
+    }
+}
+
+/// A page of the items belonging to a parent: `(parent id, offset, limit)`.
+type PageKey = (i64, i64, i64);
+
+/// The parent ids of `keys`, grouped by the page window they ask for.
+fn group_by_window(keys: &[PageKey]) -> HashMap<(i64, i64), Vec<i64>> {
+    let mut windows: HashMap<(i64, i64), Vec<i64>> = HashMap::new();
+    for &(id, offset, limit) in keys {
+        windows.entry((offset, limit)).or_default().push(id);
+    }
+    windows
+}
+
+pub struct DaoProposalsLoader;
+
+#[async_trait::async_trait]
+impl Loader<PageKey> for DaoProposalsLoader {
+    type Value = Vec<Proposals>;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[PageKey]) -> Result<HashMap<PageKey, Self::Value>, Self::Error> {
+        let windows = group_by_window(keys);
+        with_conn(move |conn| {
+            let mut ret: HashMap<PageKey, Self::Value> = HashMap::new();
+            for ((offset, limit), dao_ids) in windows {
+                for proposal in get_proposals_pages_by_dao_ids(conn, &dao_ids, offset, limit)? {
+                    ret.entry((proposal.dao_id, offset, limit))
+                        .or_default()
+                        .push(proposal);
+                }
+            }
+            Ok(ret)
+        })
+        .await
+        .map_err(Arc::new)
+    }
+}
+
+pub struct ProposalLoader;
+
+#[async_trait::async_trait]
+impl Loader<i64> for ProposalLoader {
+    type Value = Proposals;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, Self::Value>, Self::Error> {
+        let keys = keys.to_vec();
+        let proposals = with_conn(move |conn| get_proposals_by_ids(conn, &keys))
+            .await
+            .map_err(Arc::new)?;
+        Ok(proposals
+            .into_iter()
+            .map(|proposal| (proposal.id, proposal))
+            .collect())
+    }
+}
+
+pub struct ProposalVotesLoader;
+
+#[async_trait::async_trait]
+impl Loader<PageKey> for ProposalVotesLoader {
+    type Value = Vec<Votes>;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[PageKey]) -> Result<HashMap<PageKey, Self::Value>, Self::Error> {
+        let windows = group_by_window(keys);
+        with_conn(move |conn| {
+            let mut ret: HashMap<PageKey, Self::Value> = HashMap::new();
+            for ((offset, limit), proposal_ids) in windows {
+                for vote in get_votes_pages_by_proposal_ids(conn, &proposal_ids, offset, limit)? {
+                    ret.entry((vote.proposal_id, offset, limit))
+                        .or_default()
+                        .push(vote);
+                }
+            }
+            Ok(ret)
+        })
+        .await
+        .map_err(Arc::new)
+    }
+}
+
+pub struct TokenInfoLoader;
+
+#[async_trait::async_trait]
+impl Loader<i64> for TokenInfoLoader {
+    type Value = TokenInfos;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, Self::Value>, Self::Error> {
+        let keys = keys.to_vec();
+        let token_infos = with_conn(move |conn| get_token_infos_by_ids(conn, &keys))
+            .await
+            .map_err(Arc::new)?;
+        Ok(token_infos
+            .into_iter()
+            .map(|token_info| (token_info.id, token_info))
+            .collect())
+    }
+}
+
+pub struct ProfileLoader;
+
+#[async_trait::async_trait]
+impl Loader<String> for ProfileLoader {
+    type Value = Profiles;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        let keys = keys.to_vec();
+        let profiles = with_conn(move |conn| get_profiles_by_addresses(conn, &keys))
+            .await
+            .map_err(Arc::new)?;
+        Ok(profiles
+            .into_iter()
+            .map(|profile| (profile.address.clone(), profile))
+            .collect())
+    }
+}
+
+pub struct CreatorDaosLoader;
+
+#[async_trait::async_trait]
+impl Loader<String> for CreatorDaosLoader {
+    type Value = Vec<Daos>;
+    type Error = Arc<anyhow::Error>;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        let keys = keys.to_vec();
+        let daos = with_conn(move |conn| get_daos_by_creators(conn, &keys))
+            .await
+            .map_err(Arc::new)?;
+
+        let mut ret: HashMap<String, Self::Value> = HashMap::new();
+        for dao in daos {
+            ret.entry(dao.creator.clone()).or_default().push(dao);
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn introspection_fits_the_query_limits() {
+        // As deep as the `TypeRef` fragment of the playground's introspection.
+        let response = build_schema()
+            .execute(
+                "{ __schema { types { fields { args { type { ofType { ofType { ofType { ofType \
+                { ofType { ofType { ofType { name } } } } } } } } } } } } }",
+            )
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+    }
+
+    #[tokio::test]
+    async fn rejects_queries_over_the_complexity_limit() {
+        // Rejected before any resolver touches the database.
+        let response = build_schema()
+            .execute("{ daos(limit: 100) { proposals(limit: 100) { votes { key } } } }")
+            .await;
+        assert_eq!(response.errors.len(), 1);
+        assert!(response.errors[0].message.contains("too complex"));
+    }
+
+    #[tokio::test]
+    async fn rejects_queries_over_the_depth_limit() {
+        let response = build_schema()
+            .execute(
+                "{ dao(id: 1) { tokenInfo { dao { tokenInfo { dao { tokenInfo { dao { tokenInfo \
+                { dao { tokenInfo { dao { tokenInfo { dao { tokenInfo { dao { tokenInfo { id } } \
+                } } } } } } } } } } } } } } }",
+            )
+            .await;
+        assert_eq!(response.errors.len(), 1);
+        assert!(response.errors[0].message.contains("nested too deep"));
+    }
+}
@@ -1,28 +1,72 @@
+use crate::amount::Amount;
 use crate::database::{get_balances_by_owner, get_pledgers_by_token_info_id, get_token_info_by_id};
-use crate::models::{Balances, StakeAmounts};
+use crate::events::{subscribe, Topic};
+use crate::models::{Balances, Provenance, RespTotal, StakeAmounts};
 use crate::program_handler::bhp256_hash_address;
 use crate::{
     database::{
         get_all_dao_ids, get_all_proposal_ids, get_creating_dao_proposal_ids, get_dao_by_id,
-        get_dao_proposal_ids_by_dao_id, get_funds_total, get_pledgers_total,
+        get_dao_proposal_ids_by_dao_id, get_funds_summary, get_pledgers_summary,
         get_profile_by_address, get_proposals_by_proposal_id, get_records_by_height,
-        get_stake_funds_total, get_stakes_by_owner, insert_profile, insert_token_info,
-        update_profile, upsert_profile, POOL,
+        get_stake_funds_summary, get_stakes_by_owner, insert_profile, insert_token_info,
+        update_profile, upsert_profile, with_conn,
     },
-    models::{Daos, Input, Output, Profiles, Proposals, RespRecords, TokenInfos},
+    models::{Daos, Input, Output, Profiles, Proposals, Record, RespRecords, TokenInfos},
 };
 use axum::{
     extract::{Json as PostJson, Path, Query},
-    response::Json,
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json,
+    },
 };
-use diesel::{r2d2::ConnectionManager, PgConnection};
-use r2d2::PooledConnection;
+use futures03::{Stream, StreamExt};
 use std::{collections::HashMap, str::FromStr};
 
+/// Converts a stored record into its API representation, decoding the JSON
+/// encoded inputs and outputs.
+pub fn resp_record(record: &Record) -> Result<RespRecords, serde_json::Error> {
+    let inputs: Vec<Input> = serde_json::from_value(record.inputs.clone())?;
+    let outputs: Vec<Output> = serde_json::from_value(record.outputs.clone())?;
+    let record_values = outputs
+        .iter()
+        .filter_map(|output| {
+            if output.r#type.eq("record") {
+                Some(output.value.clone())
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    Ok(RespRecords {
+        records: record_values,
+        transaction_id: record.transaction_id.clone(),
+        transition_id: record.transition_id.clone(),
+        network: record.network,
+        height: record.height,
+        timestamp: record.timestamp,
+        inputs,
+        outputs,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/records",
+    tag = "records",
+    params(
+        ("start_block" = Option<i64>, Query, description = "First block height, inclusive"),
+        ("end_block" = Option<i64>, Query, description = "Last block height, inclusive"),
+    ),
+    responses(
+        (status = 200, description = "Records indexed in the block range", body = [RespRecords]),
+    )
+)]
 pub async fn records_handler(
     Query(params): Query<HashMap<String, String>>,
 ) -> Json<Vec<RespRecords>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
     let default_start_block = "0".to_string();
     let default_end_block = i64::MAX.to_string();
     let start_block = params.get("start_block").unwrap_or(&default_start_block);
@@ -30,65 +74,107 @@ pub async fn records_handler(
     let end_block = params.get("end_block").unwrap_or(&default_end_block);
     let end_block = i64::from_str(&end_block).unwrap_or(i64::MAX);
 
-    let records = get_records_by_height(&mut conn, start_block, end_block).unwrap();
+    let records = with_conn(move |conn| get_records_by_height(conn, start_block, end_block))
+        .await
+        .unwrap();
 
     let results = records
         .iter()
-        .map(|record| {
-            let inputs: Vec<Input> = serde_json::from_str(&record.inputs).unwrap();
-            let outputs: Vec<Output> = serde_json::from_str(&record.outputs).unwrap();
-            let record_values = outputs
-                .iter()
-                .filter_map(|output| {
-                    if output.r#type.eq("record") {
-                        Some(output.value.clone())
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-
-            RespRecords {
-                records: record_values,
-                transaction_id: record.transaction_id.clone(),
-                transition_id: record.transition_id.clone(),
-                network: record.network,
-                height: record.height,
-                timestamp: record.timestamp,
-                inputs: inputs,
-                outputs: outputs,
-            }
-        })
+        .map(|record| resp_record(record).unwrap())
         .collect::<Vec<RespRecords>>();
 
     Json(results)
 }
 
-pub async fn get_profile_handler(Path(address): Path<String>) -> Json<Profiles> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
+/// Server-Sent Events stream of indexer events, e.g. `/events?topic=votes&proposal_id=1`.
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "events",
+    params(
+        ("topic" = String, Query, description = "One of `blocks`, `proposal_status`, `votes` or `balances`"),
+        ("dao_id" = Option<i64>, Query, description = "Narrows `proposal_status` to one DAO"),
+        ("proposal_id" = Option<i64>, Query, description = "Narrows `votes` to one proposal"),
+        ("address" = Option<String>, Query, description = "Narrows `balances` to one address"),
+    ),
+    responses(
+        (status = 200, description = "Server-Sent Events stream, one JSON encoded event per message", content_type = "text/event-stream", body = IndexerEvent),
+        (status = 400, description = "Unknown topic or invalid filter", body = String),
+    )
+)]
+pub async fn events_handler(
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Sse<impl Stream<Item = Result<Event, serde_json::Error>>>, (StatusCode, String)> {
+    let topic =
+        Topic::from_params(&params).map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+
+    let stream =
+        subscribe(topic).map(|event| Event::default().event(event.topic_name()).json_data(&event));
 
-    let profiles = get_profile_by_address(&mut conn, address).unwrap();
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/profile/{address}",
+    tag = "profiles",
+    params(
+        ("address" = String, Path, description = "Aleo address"),
+    ),
+    responses(
+        (status = 200, description = "Profile of the address", body = Profiles),
+    )
+)]
+pub async fn get_profile_handler(Path(address): Path<String>) -> Json<Profiles> {
+    let mut profiles = with_conn(move |conn| get_profile_by_address(conn, address))
+        .await
+        .unwrap();
 
+    profiles.clear_provenance();
     Json(profiles)
 }
 
+#[utoipa::path(
+    get,
+    path = "/all-dao-ids",
+    tag = "daos",
+    responses(
+        (status = 200, description = "Ids of every DAO", body = [i64]),
+    )
+)]
 pub async fn get_all_dao_ids_handler() -> Json<Vec<i64>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-
-    let dao_ids = get_all_dao_ids(&mut conn);
+    let dao_ids = with_conn(get_all_dao_ids).await;
     Json(dao_ids.unwrap())
 }
 
+#[utoipa::path(
+    get,
+    path = "/daos",
+    tag = "daos",
+    params(
+        ("id-array" = String, Query, description = "JSON array of DAO ids, e.g. `[1,2]`"),
+    ),
+    responses(
+        (status = 200, description = "DAOs in the requested order, unknown ids yield an empty DAO with id 0", body = [Daos]),
+    )
+)]
 pub async fn batch_get_dao_handler(
     Query(params): Query<HashMap<String, String>>,
 ) -> Json<Vec<Daos>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-    let mut ret_vec_dao: Vec<Daos> = Vec::new();
     let id_array: Vec<i64> =
         serde_json::from_str(&params.get("id-array").unwrap().to_string()).unwrap();
 
-    for id in id_array {
-        let dao = get_dao_by_id(&mut conn, id);
+    let daos = with_conn(move |conn| {
+        Ok(id_array
+            .into_iter()
+            .map(|id| get_dao_by_id(conn, id))
+            .collect::<Vec<_>>())
+    })
+    .await
+    .unwrap();
+
+    let mut ret_vec_dao: Vec<Daos> = Vec::new();
+    for dao in daos {
         match dao {
             Ok(dao) => {
                 ret_vec_dao.push(dao);
@@ -103,30 +189,52 @@ pub async fn batch_get_dao_handler(
                     icon: "".to_string(),
                     description: "".to_string(),
                     official_link: "".to_string(),
-                    proposal_count: 0,
-                    pass_proposal_count: 0,
-                    vote_count: 0,
-                    passed_votes_proportion: 0,
-                    passed_tokens_proportion: 0,
+                    proposal_count: Amount::default(),
+                    pass_proposal_count: Amount::default(),
+                    vote_count: Amount::default(),
+                    passed_votes_proportion: Amount::default(),
+                    passed_tokens_proportion: Amount::default(),
+                    updated_at_height: None,
+                    updated_by_transition_id: None,
+                    updated_at_timestamp: None,
                 };
 
                 ret_vec_dao.push(empty_dao);
             }
         }
     }
+    ret_vec_dao.clear_provenance();
     Json(ret_vec_dao)
 }
 
+#[utoipa::path(
+    get,
+    path = "/token-ids",
+    tag = "daos",
+    params(
+        ("dao-id-array" = String, Query, description = "JSON array of DAO ids, e.g. `[1,2]`"),
+    ),
+    responses(
+        (status = 200, description = "Token info id of each DAO, -1 for unknown DAOs", body = [i64]),
+    )
+)]
 pub async fn batch_get_token_id_of_dao_handler(
     Query(params): Query<HashMap<String, String>>,
 ) -> Json<Vec<i64>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-    let mut ret_token_ids: Vec<i64> = Vec::new();
     let id_array: Vec<i64> =
         serde_json::from_str(&params.get("dao-id-array").unwrap().to_string()).unwrap();
 
-    for id in id_array {
-        let dao = get_dao_by_id(&mut conn, id);
+    let daos = with_conn(move |conn| {
+        Ok(id_array
+            .into_iter()
+            .map(|id| get_dao_by_id(conn, id))
+            .collect::<Vec<_>>())
+    })
+    .await
+    .unwrap();
+
+    let mut ret_token_ids: Vec<i64> = Vec::new();
+    for dao in daos {
         match dao {
             Ok(dao) => {
                 ret_token_ids.push(dao.token_info_id);
@@ -139,17 +247,34 @@ pub async fn batch_get_token_id_of_dao_handler(
     Json(ret_token_ids)
 }
 
+#[utoipa::path(
+    get,
+    path = "/dao_proposal_ids",
+    tag = "proposals",
+    params(
+        ("dao-id-array" = String, Query, description = "JSON array of DAO ids, e.g. `[1,2]`"),
+    ),
+    responses(
+        (status = 200, description = "Proposal ids of each DAO", body = [Vec<i64>]),
+    )
+)]
 pub async fn batch_get_proposal_id_of_dao_handler(
     Query(params): Query<HashMap<String, String>>,
 ) -> Json<Vec<Vec<i64>>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-    let mut ret_proposal_ids: Vec<Vec<i64>> = Vec::new();
-
     let id_array: Vec<i64> =
         serde_json::from_str(&params.get("dao-id-array").unwrap().to_string()).unwrap();
 
-    for id in id_array {
-        let proposal_ids = get_dao_proposal_ids_by_dao_id(&mut conn, id);
+    let dao_proposal_ids = with_conn(move |conn| {
+        Ok(id_array
+            .into_iter()
+            .map(|id| get_dao_proposal_ids_by_dao_id(conn, id))
+            .collect::<Vec<_>>())
+    })
+    .await
+    .unwrap();
+
+    let mut ret_proposal_ids: Vec<Vec<i64>> = Vec::new();
+    for proposal_ids in dao_proposal_ids {
         match proposal_ids {
             Ok(ids) => ret_proposal_ids.push(ids),
             Err(err) => {
@@ -161,16 +286,34 @@ pub async fn batch_get_proposal_id_of_dao_handler(
     Json(ret_proposal_ids)
 }
 
+#[utoipa::path(
+    get,
+    path = "/token-info",
+    tag = "tokens",
+    params(
+        ("id-array" = String, Query, description = "JSON array of token info ids, e.g. `[1,2]`"),
+    ),
+    responses(
+        (status = 200, description = "Token infos in the requested order, unknown ids yield an empty token info with id 0", body = [TokenInfos]),
+    )
+)]
 pub async fn batch_get_token_info_handler(
     Query(params): Query<HashMap<String, String>>,
 ) -> Json<Vec<TokenInfos>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-    let mut ret_token_infos: Vec<TokenInfos> = Vec::new();
     let id_array: Vec<i64> =
         serde_json::from_str(&params.get("id-array").unwrap().to_string()).unwrap();
 
-    for id in id_array {
-        let token_info = get_token_info_by_id(&mut conn, id);
+    let token_infos = with_conn(move |conn| {
+        Ok(id_array
+            .into_iter()
+            .map(|id| get_token_info_by_id(conn, id))
+            .collect::<Vec<_>>())
+    })
+    .await
+    .unwrap();
+
+    let mut ret_token_infos: Vec<TokenInfos> = Vec::new();
+    for token_info in token_infos {
         match token_info {
             Ok(token_info) => {
                 ret_token_infos.push(token_info);
@@ -180,83 +323,173 @@ pub async fn batch_get_token_info_handler(
                     id: 0,
                     name: "".to_string(),
                     symbol: "".to_string(),
-                    supply: 0,
+                    supply: Amount::default(),
                     decimals: 0,
-                    max_mint_amount: 0,
-                    minted_amount: 0,
+                    max_mint_amount: Amount::default(),
+                    minted_amount: Amount::default(),
                     dao_id: 0,
                     only_creator_can_mint: false,
+                    updated_at_height: None,
+                    updated_by_transition_id: None,
+                    updated_at_timestamp: None,
                 });
             }
         }
     }
 
+    ret_token_infos.clear_provenance();
     Json(ret_token_infos)
 }
 
+#[utoipa::path(
+    get,
+    path = "/balances/{address}",
+    tag = "tokens",
+    params(
+        ("address" = String, Path, description = "Aleo address"),
+    ),
+    responses(
+        (status = 200, description = "Balances held by the address", body = [Balances]),
+    )
+)]
 pub async fn get_balances_handler(Path(address): Path<String>) -> Json<Vec<Balances>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
     let hash_addr = bhp256_hash_address(&address).unwrap();
-    let ret_balances = get_balances_by_owner(&mut conn, hash_addr.to_string()).unwrap();
+    let mut ret_balances =
+        with_conn(move |conn| get_balances_by_owner(conn, hash_addr.to_string()))
+            .await
+            .unwrap();
 
+    ret_balances.clear_provenance();
     Json(ret_balances)
 }
 
+#[utoipa::path(
+    get,
+    path = "/stakes/{address}",
+    tag = "tokens",
+    params(
+        ("address" = String, Path, description = "Aleo address"),
+    ),
+    responses(
+        (status = 200, description = "Stakes held by the address", body = [StakeAmounts]),
+    )
+)]
 pub async fn get_stakes_handler(Path(address): Path<String>) -> Json<Vec<StakeAmounts>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
     let hash_addr = bhp256_hash_address(&address).unwrap();
-    let ret_stakes = get_stakes_by_owner(&mut conn, hash_addr.to_string()).unwrap();
+    let mut ret_stakes = with_conn(move |conn| get_stakes_by_owner(conn, hash_addr.to_string()))
+        .await
+        .unwrap();
 
+    ret_stakes.clear_provenance();
     Json(ret_stakes)
 }
 
+#[utoipa::path(
+    get,
+    path = "/pledgers",
+    tag = "stats",
+    params(
+        ("token-info-id-array" = String, Query, description = "JSON array of token info ids, e.g. `[1,2]`"),
+    ),
+    responses(
+        (status = 200, description = "Number of distinct stakers of each token", body = [i64]),
+    )
+)]
 pub async fn batch_get_pledgers_by_token_info_id(
     Query(params): Query<HashMap<String, String>>,
 ) -> Json<Vec<i64>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-    let mut ret_pledgers: Vec<i64> = Vec::new();
     let token_info_id_array: Vec<i64> =
         serde_json::from_str(&params.get("token-info-id-array").unwrap().to_string()).unwrap();
-    for id in token_info_id_array {
-        let pledgers = get_pledgers_by_token_info_id(&mut conn, id).unwrap_or_default();
-        ret_pledgers.push(pledgers);
-    }
+    let ret_pledgers = with_conn(move |conn| {
+        Ok(token_info_id_array
+            .into_iter()
+            .map(|id| get_pledgers_by_token_info_id(conn, id).unwrap_or_default())
+            .collect::<Vec<i64>>())
+    })
+    .await
+    .unwrap();
     Json(ret_pledgers)
 }
 
-pub async fn get_pledgers_total_handler() -> String {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-    let ret_pledgers_total = get_pledgers_total(&mut conn).unwrap();
-    ret_pledgers_total
+#[utoipa::path(
+    get,
+    path = "/pledgers-total",
+    tag = "stats",
+    responses(
+        (status = 200, description = "Number of distinct stakers, overall and broken down per token info and per DAO", body = RespTotal),
+    )
+)]
+pub async fn get_pledgers_total_handler() -> Json<RespTotal> {
+    let ret_pledgers_total = with_conn(get_pledgers_summary).await.unwrap();
+    Json(ret_pledgers_total)
 }
 
-pub async fn get_stake_funds_total_handler() -> String {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-    let ret_stake_funds_total = get_stake_funds_total(&mut conn).unwrap();
-    ret_stake_funds_total
+#[utoipa::path(
+    get,
+    path = "/stake-funds-total",
+    tag = "stats",
+    responses(
+        (status = 200, description = "Sum of every staked amount, overall and broken down per token info and per DAO", body = RespTotal),
+    )
+)]
+pub async fn get_stake_funds_total_handler() -> Json<RespTotal> {
+    let ret_stake_funds_total = with_conn(get_stake_funds_summary).await.unwrap();
+    Json(ret_stake_funds_total)
 }
 
-pub async fn get_funds_total_handler() -> String {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-    let ret_stake_funds_total = get_funds_total(&mut conn).unwrap();
-    ret_stake_funds_total
+#[utoipa::path(
+    get,
+    path = "/funds-total",
+    tag = "stats",
+    responses(
+        (status = 200, description = "Sum of every balance, overall and broken down per token info and per DAO", body = RespTotal),
+    )
+)]
+pub async fn get_funds_total_handler() -> Json<RespTotal> {
+    let ret_funds_total = with_conn(get_funds_summary).await.unwrap();
+    Json(ret_funds_total)
 }
 
+#[utoipa::path(
+    get,
+    path = "/proposal-ids/creating-dao",
+    tag = "proposals",
+    responses(
+        (status = 200, description = "Ids of pending DAO creation proposals", body = [i64]),
+    )
+)]
 pub async fn get_creating_dao_proposal_ids_handler() -> Json<Vec<i64>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-    let prop_id = get_creating_dao_proposal_ids(&mut conn).unwrap();
+    let prop_id = with_conn(get_creating_dao_proposal_ids).await.unwrap();
     Json(prop_id)
 }
 
+#[utoipa::path(
+    get,
+    path = "/proposals",
+    tag = "proposals",
+    params(
+        ("id-array" = String, Query, description = "JSON array of proposal ids, e.g. `[1,2]`"),
+    ),
+    responses(
+        (status = 200, description = "Proposals in the requested order, unknown ids yield an empty proposal with id 0", body = [Proposals]),
+    )
+)]
 pub async fn batch_get_proposals_handler(
     Query(params): Query<HashMap<String, String>>,
 ) -> Json<Vec<Proposals>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-    let mut ret_proposals: Vec<Proposals> = Vec::new();
     let proposal_id_array: Vec<i64> =
         serde_json::from_str(&params.get("id-array").unwrap().to_string()).unwrap();
-    for id in proposal_id_array {
-        let proposal = get_proposals_by_proposal_id(&mut conn, id);
+    let proposals = with_conn(move |conn| {
+        Ok(proposal_id_array
+            .into_iter()
+            .map(|id| get_proposals_by_proposal_id(conn, id))
+            .collect::<Vec<_>>())
+    })
+    .await
+    .unwrap();
+
+    let mut ret_proposals: Vec<Proposals> = Vec::new();
+    for proposal in proposals {
         match proposal {
             Ok(proposal) => ret_proposals.push(proposal),
             Err(err) => {
@@ -270,21 +503,31 @@ pub async fn batch_get_proposals_handler(
                     created: 0,
                     duration: 0,
                     type_: 0,
-                    adopt: 0,
-                    reject: 0,
+                    adopt: Amount::default(),
+                    reject: Amount::default(),
                     status: 0,
+                    updated_at_height: None,
+                    updated_by_transition_id: None,
+                    updated_at_timestamp: None,
                 };
                 ret_proposals.push(empty_proposals)
             }
         }
     }
+    ret_proposals.clear_provenance();
     Json(ret_proposals)
 }
 
+#[utoipa::path(
+    get,
+    path = "/all-proposal-ids",
+    tag = "proposals",
+    responses(
+        (status = 200, description = "Ids of every proposal", body = [i64]),
+    )
+)]
 pub async fn get_all_proposal_ids_handler() -> Json<Vec<i64>> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-
-    let ret_proposal_ids = get_all_proposal_ids(&mut conn).unwrap();
+    let ret_proposal_ids = with_conn(get_all_proposal_ids).await.unwrap();
     Json(ret_proposal_ids)
 }
 
@@ -300,9 +543,30 @@ pub fn string_to_i64(input: &String) -> i64 {
     }
 }
 
-pub async fn create_profile_handler(Query(params): Query<HashMap<String, String>>) -> Json<String> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
+pub fn string_to_amount(input: &String) -> Amount {
+    match input.parse::<Amount>() {
+        Ok(amount) => amount,
+        Err(error) => {
+            panic!("Parsing error: {:?}", error);
+        }
+    }
+}
 
+#[utoipa::path(
+    get,
+    path = "/crate_profile",
+    tag = "profiles",
+    params(
+        ("address" = String, Query, description = "Aleo address"),
+        ("name" = String, Query, description = "Display name"),
+        ("avatar" = String, Query, description = "Avatar url"),
+        ("bio" = String, Query, description = "Short biography"),
+    ),
+    responses(
+        (status = 200, description = "Insertion status", body = String),
+    )
+)]
+pub async fn create_profile_handler(Query(params): Query<HashMap<String, String>>) -> Json<String> {
     let addr = params.get("address").unwrap().to_string();
     let names = params.get("name").unwrap().to_string();
     let avatars = params.get("avatar").unwrap().to_string();
@@ -312,17 +576,39 @@ pub async fn create_profile_handler(Query(params): Query<HashMap<String, String>
         name: names,
         avatar: avatars,
         bio: bios,
+        updated_at_height: None,
+        updated_by_transition_id: None,
+        updated_at_timestamp: None,
     };
 
-    let status = insert_profile(&mut conn, profile).unwrap();
+    let status = with_conn(move |conn| insert_profile(conn, profile))
+        .await
+        .unwrap();
     Json(status.to_string())
 }
 
+#[utoipa::path(
+    get,
+    path = "/create_token_info",
+    tag = "tokens",
+    params(
+        ("id" = i64, Query, description = "Token info id"),
+        ("name" = String, Query, description = "Token name"),
+        ("symbol" = String, Query, description = "Token symbol"),
+        ("supply" = String, Query, description = "Total supply"),
+        ("decimals" = i64, Query, description = "Number of decimals"),
+        ("max_mint_amount" = String, Query, description = "Maximum amount minted at once"),
+        ("minted_amount" = String, Query, description = "Amount already minted"),
+        ("dao_id" = i64, Query, description = "Id of the owning DAO"),
+        ("only_creator_can_mint" = Option<String>, Query, description = "Present to restrict minting to the DAO creator"),
+    ),
+    responses(
+        (status = 200, description = "Insertion status", body = String),
+    )
+)]
 pub async fn create_token_info_handler(
     Query(params): Query<HashMap<String, String>>,
 ) -> Json<String> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-
     let id = params.get("id").unwrap().to_string();
     let name = params.get("name").unwrap().to_string();
     let symbol = params.get("symbol").unwrap().to_string();
@@ -340,21 +626,38 @@ pub async fn create_token_info_handler(
         id: string_to_i64(&id),
         name,
         symbol,
-        supply: string_to_i64(&supply),
+        supply: string_to_amount(&supply),
         decimals: string_to_i64(&decimals),
-        max_mint_amount: string_to_i64(&max_mint_amount),
-        minted_amount: string_to_i64(&minted_amount),
+        max_mint_amount: string_to_amount(&max_mint_amount),
+        minted_amount: string_to_amount(&minted_amount),
         dao_id: string_to_i64(&dao_id),
         only_creator_can_mint: only_creator_can_mints,
+        updated_at_height: None,
+        updated_by_transition_id: None,
+        updated_at_timestamp: None,
     };
 
-    let status = insert_token_info(&mut conn, token_info).unwrap();
+    let status = with_conn(move |conn| insert_token_info(conn, token_info))
+        .await
+        .unwrap();
     Json(status.to_string())
 }
 
+#[utoipa::path(
+    get,
+    path = "/update_profile",
+    tag = "profiles",
+    params(
+        ("address" = String, Query, description = "Aleo address"),
+        ("name" = String, Query, description = "Display name"),
+        ("avatar" = String, Query, description = "Avatar url"),
+        ("bio" = String, Query, description = "Short biography"),
+    ),
+    responses(
+        (status = 200, description = "Update status", body = String),
+    )
+)]
 pub async fn update_profile_handler(Query(params): Query<HashMap<String, String>>) -> Json<String> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-
     let addr = params.get("address").unwrap().to_string();
     let names = params.get("name").unwrap().to_string();
     let avatars = params.get("avatar").unwrap().to_string();
@@ -365,15 +668,32 @@ pub async fn update_profile_handler(Query(params): Query<HashMap<String, String>
         name: names,
         avatar: avatars,
         bio: bios,
+        updated_at_height: None,
+        updated_by_transition_id: None,
+        updated_at_timestamp: None,
     };
 
-    let status = update_profile(&mut conn, profile).unwrap();
+    let status = with_conn(move |conn| update_profile(conn, profile))
+        .await
+        .unwrap();
     Json(status.to_string())
 }
 
+#[utoipa::path(
+    get,
+    path = "/upsert_profile",
+    tag = "profiles",
+    params(
+        ("address" = String, Query, description = "Aleo address"),
+        ("name" = String, Query, description = "Display name"),
+        ("avatar" = String, Query, description = "Avatar url"),
+        ("bio" = String, Query, description = "Short biography"),
+    ),
+    responses(
+        (status = 200, description = "Upsert status", body = String),
+    )
+)]
 pub async fn upsert_profile_handler(Query(params): Query<HashMap<String, String>>) -> Json<String> {
-    let mut conn: PooledConnection<ConnectionManager<PgConnection>> = POOL.get().unwrap();
-
     let addr = params.get("address").unwrap().to_string();
     let names = params.get("name").unwrap().to_string();
     let avatars = params.get("avatar").unwrap().to_string();
@@ -384,8 +704,13 @@ pub async fn upsert_profile_handler(Query(params): Query<HashMap<String, String>
         name: names,
         avatar: avatars,
         bio: bios,
+        updated_at_height: None,
+        updated_by_transition_id: None,
+        updated_at_timestamp: None,
     };
 
-    let status = upsert_profile(&mut conn, profile).unwrap();
+    let status = with_conn(move |conn| upsert_profile(conn, profile))
+        .await
+        .unwrap();
     Json(status.to_string())
 }
@@ -1,34 +1,83 @@
 extern crate diesel;
 
-use crate::{program_handler::program_handler, routes::routes};
+use crate::routes::routes;
 use anyhow::{format_err, Context, Error};
+use axum::{routing::get, Router};
+use capture::{CaptureArgs, CaptureWriter, ReplayStep, ReplayedMappings, Step};
+use chrono::{NaiveDateTime, Utc};
 use clap::Parser;
-use cli::{Cli, Commands};
-use database::{batch_insert_records, POOL};
+use cli::{Cli, Commands, ExportSinkArgs, SinkArgs};
+use database::PoolSettings;
+use events::listen_notifications;
+use export::{export_table, ExportOptions, ExportSink};
 use futures03::StreamExt;
 use http::Method;
+use program_handler::{retry_failed_record, retry_failed_records};
 use prost::Message;
-use proto::{module_output::Data as ModuleOutputData, BlockScopedData, Records};
-use std::{env, net::SocketAddr, str::FromStr, sync::Arc};
+use proto::{module_output::Data as ModuleOutputData, BlockScopedData, ForkStep, Records};
+use rest_api::RestApi;
+use sink::{DatabaseSink, KafkaSink, NdjsonSink, Sink, SinkBlock, SinkKind, Sinks};
+use std::{
+    env,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use storage::{run_with_storage, with_storage, StorageConfig};
 use substreams::SubstreamsEndpoint;
 use substreams_stream::{BlockResponse, SubstreamsStream};
 use tower_http::cors::{Any, CorsLayer};
+use tracing::{error, field, info, info_span, warn};
 
+mod amount;
+mod capture;
 mod cli;
 mod database;
+mod events;
+mod export;
+mod graphql;
 mod handlers;
 mod mappings;
+mod metrics;
 mod models;
+mod openapi;
+mod pagination;
 mod program_handler;
 mod proto;
+mod rest_api;
+#[cfg(test)]
+mod rest_api_mock;
 mod routes;
 mod schema;
+mod sink;
+mod sqlite;
+mod status;
+mod storage;
 mod substreams;
+#[cfg(test)]
+mod substreams_mock;
 mod substreams_stream;
+mod telemetry;
+mod v1;
+mod view_keys;
+
+/// How often `sync` polls the Aleo REST API for the head block height.
+const HEAD_POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// How often `sync` looks for failed records that are due for a retry.
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+/// Failed records retried at most per round.
+const RETRY_BATCH_SIZE: i64 = 100;
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
+    telemetry::init(&cli.telemetry).unwrap();
+    metrics::register();
+    if let Err(err) = PoolSettings::from_env() {
+        error!("{:#}", err);
+        std::process::exit(1);
+    }
 
     match &cli.command {
         Some(Commands::Sync {
@@ -38,14 +87,25 @@ async fn main() {
             module_name,
             start_block,
             end_block,
+            sinks,
+            export,
+            capture,
+            metrics_addr,
         }) => {
+            if let Some(addr) = metrics_addr {
+                tokio::spawn(serve_metrics(addr.clone()));
+            }
+            let rest_api = RestApi::new(rest_api.clone(), &cli.rest_api).unwrap();
             sync(
-                rest_api,
+                &rest_api,
                 endpoint_url,
                 package_file,
                 module_name,
                 start_block,
                 end_block,
+                sinks,
+                export,
+                capture,
             )
             .await;
         }
@@ -55,7 +115,9 @@ async fn main() {
             port,
             host,
         }) => {
-            serve(rest_api, host, port).await;
+            require_postgres("serve");
+            let rest_api = RestApi::new(rest_api.clone(), &cli.rest_api).unwrap();
+            serve(&rest_api, host, port, true).await;
         }
 
         Some(Commands::All {
@@ -67,32 +129,184 @@ async fn main() {
             end_block,
             port,
             host,
+            sinks,
+            export,
+            capture,
         }) => {
+            require_postgres("all");
+            let rest_api = RestApi::new(rest_api.clone(), &cli.rest_api).unwrap();
             tokio::join!(
                 sync(
-                    rest_api,
+                    &rest_api,
                     endpoint_url,
                     package_file,
                     module_name,
                     start_block,
                     end_block,
+                    sinks,
+                    export,
+                    capture,
                 ),
-                serve(rest_api, host, port),
+                serve(&rest_api, host, port, false),
             );
         }
 
+        Some(Commands::CheckChain {
+            start_block,
+            end_block,
+        }) => {
+            if let Err(err) = check_chain(*start_block, *end_block).await {
+                error!("Check chain failed {:#}", err);
+                std::process::exit(1);
+            }
+        }
+
+        Some(Commands::Export {
+            tables,
+            out_dir,
+            format,
+            partition_size,
+            start_block,
+            end_block,
+        }) => {
+            require_postgres("export");
+            let options = ExportOptions {
+                out_dir: out_dir.into(),
+                format: *format,
+                partition_size: *partition_size,
+            };
+            if let Err(err) = export(tables.clone(), *start_block, *end_block, options).await {
+                error!("Export failed {:#}", err);
+                std::process::exit(1);
+            }
+        }
+
+        Some(Commands::Retry {
+            rest_api,
+            transition_ids,
+        }) => match retry(
+            RestApi::new(rest_api.clone(), &cli.rest_api)
+                .unwrap()
+                .without_backoff(),
+            transition_ids.clone(),
+        )
+        .await
+        {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(err) => {
+                error!("Retry failed {:#}", err);
+                std::process::exit(1);
+            }
+        },
+
+        Some(Commands::ListFailures { limit }) => {
+            if let Err(err) = list_failures(*limit).await {
+                error!("List failures failed {:#}", err);
+                std::process::exit(1);
+            }
+        }
+
         None => {}
     }
+
+    telemetry::shutdown();
+}
+
+/// Exits unless `DATABASE_URL` is a Postgres database. SQLite only backs
+/// `sync` and the commands on failed records and chain checks; the query API
+/// and the exports read Postgres directly.
+fn require_postgres(command: &str) {
+    if let StorageConfig::Sqlite(_) = StorageConfig::from_env() {
+        error!(
+            "{} needs Postgres, DATABASE_URL is a SQLite database",
+            command
+        );
+        std::process::exit(1);
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn sync(
-    rest_api: &String,
+    rest_api: &RestApi,
     endpoint_url: &String,
     package_file: &String,
     module_name: &String,
     start_block: &i64,
     end_block: &u64,
+    sinks: &SinkArgs,
+    export: &ExportSinkArgs,
+    capture: &CaptureArgs,
 ) {
+    let program_id = env::var("ALEO_PROGRAM_ID").unwrap_or_default();
+
+    let capture_writer = capture
+        .capture
+        .as_ref()
+        .map(|path| Arc::new(CaptureWriter::open(path.into()).unwrap()));
+    let replay_steps = capture
+        .replay
+        .as_ref()
+        .map(|path| capture::read(path).unwrap());
+    let replayed_mappings = ReplayedMappings::default();
+    let rest_api = match (&capture_writer, &replay_steps) {
+        (Some(capture_writer), _) => rest_api.clone().capturing(capture_writer.clone()),
+        (_, Some(_)) => rest_api.clone().replaying(replayed_mappings.clone()),
+        _ => rest_api.clone(),
+    };
+
+    status::sync_started();
+    if replay_steps.is_none() {
+        tokio::spawn(poll_head_height(rest_api.clone()));
+    }
+
+    let has_database = sinks.sinks.contains(&SinkKind::Database);
+    let sink_args = sinks.clone();
+    let sinks_rest_api = rest_api.clone();
+    // Opening sinks reads their cursors synchronously.
+    let sinks =
+        tokio::task::spawn_blocking(move || open_sinks(&sink_args, sinks_rest_api, program_id))
+            .await
+            .unwrap()
+            .unwrap();
+
+    // Sinks that are further along skip the blocks they already have.
+    let cursor: Option<String> = sinks.start_cursor();
+    let sinks = Arc::new(Mutex::new(sinks));
+
+    let export_sink = export.export_dir.as_ref().map(|dir| {
+        Arc::new(Mutex::new(ExportSink::new(
+            export.export_tables.clone(),
+            ExportOptions {
+                out_dir: dir.into(),
+                format: export.export_format,
+                partition_size: export.export_partition_size,
+            },
+        )))
+    });
+
+    if let Some(replay_steps) = replay_steps {
+        replay(
+            replay_steps,
+            &replayed_mappings,
+            &rest_api,
+            module_name,
+            *end_block,
+            sinks,
+            export_sink,
+        )
+        .await;
+        return;
+    }
+
+    if has_database {
+        tokio::spawn(retry_failed_records_loop(
+            rest_api.clone().without_backoff(),
+            sinks.clone(),
+            capture_writer.clone(),
+        ));
+    }
+
     let token_env = env::var("SUBSTREAMS_API_TOKEN").unwrap_or("".to_string());
     let mut token: Option<String> = None;
     if token_env.len() > 0 {
@@ -102,11 +316,6 @@ async fn sync(
     let package = read_package(&package_file).unwrap();
     let endpoint = Arc::new(SubstreamsEndpoint::new(&endpoint_url, token).await.unwrap());
 
-    // FIXME: Handling of the cursor is missing here. It should be loaded from
-    // the database and the SubstreamStream will correctly resume from the right
-    // block.
-    let cursor: Option<String> = None;
-
     let mut stream = SubstreamsStream::new(
         endpoint.clone(),
         cursor,
@@ -116,53 +325,392 @@ async fn sync(
         *end_block,
     );
 
-    let mut conn = POOL.get().unwrap();
-
-    let program_id = env::var("ALEO_PROGRAM_ID").unwrap_or_default();
-
     loop {
         match stream.next().await {
             None => {
-                println!("Stream consumed");
+                info!("Stream consumed");
                 break;
             }
             Some(event) => match event {
                 Err(_) => {}
                 Ok(BlockResponse::New(data)) => {
-                    println!("Consuming module output (cursor {})", data.cursor);
+                    index_block(
+                        data,
+                        false,
+                        module_name,
+                        sinks.clone(),
+                        export_sink.clone(),
+                        capture_writer.clone(),
+                    )
+                    .await
+                }
+                Ok(BlockResponse::Undo(data)) => {
+                    index_block(
+                        data,
+                        true,
+                        module_name,
+                        sinks.clone(),
+                        export_sink.clone(),
+                        capture_writer.clone(),
+                    )
+                    .await
+                }
+            },
+        }
+    }
+}
+
+/// Writes a block to the sinks, or removes it from them when it is undone by
+/// a fork, recording it to `capture_writer` first.
+async fn index_block(
+    data: BlockScopedData,
+    undo: bool,
+    module_name: &String,
+    sinks: Arc<Mutex<Sinks>>,
+    export_sink: Option<Arc<Mutex<ExportSink>>>,
+    capture_writer: Option<Arc<CaptureWriter>>,
+) {
+    let clock = data.clock.clone().unwrap_or_default();
+    let cursor = data.cursor.clone();
+    let span = info_span!(
+        "block",
+        height = clock.number,
+        cursor = %cursor,
+        record_count = field::Empty,
+    );
+    span.in_scope(|| info!("Consuming module output"));
+
+    let captured = capture_writer.map(|capture_writer| (capture_writer, data.clone()));
+    if let Some(records) = extract_records(data, module_name).unwrap() {
+        span.record("record_count", records.records.len());
+        let block = SinkBlock {
+            height: clock.number as i64,
+            block_hash: clock.id,
+            timestamp: clock.timestamp.map_or(0, |t| t.seconds),
+            cursor,
+            records,
+        };
+
+        // Indexing a block queries the database and the
+        // REST API synchronously, so it runs off the runtime.
+        tokio::task::spawn_blocking(move || -> Result<(), Error> {
+            let _entered = span.enter();
+            let mut sinks = sinks.lock().unwrap();
+            if let Some((capture_writer, data)) = captured {
+                capture_writer.block(&data)?;
+            }
+            if undo {
+                warn!("Block undone by a fork");
+                return sinks.undo_block(&block);
+            }
+            sinks.write_block(&block)?;
+            metrics::block_indexed(block.height, block.records.records.len());
+            status::block_processed(
+                block.height,
+                &block.block_hash,
+                block.timestamp,
+                &block.cursor,
+            );
+
+            if let Some(export_sink) = export_sink {
+                let mut export_sink = export_sink.lock().unwrap();
+                if let Err(err) = export_sink.block_indexed(block.height) {
+                    warn!("Export error {:#}", err);
+                    status::record_error(format!("export: {:#}", err));
+                }
+            }
+            Ok(())
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    }
+}
 
-                    match extract_records(data, &module_name).unwrap() {
-                        Some(records) => {
-                            batch_insert_records(&mut conn, &records)
-                                .context("insertion in db failed")
-                                .unwrap();
+/// Syncs from a capture file: its blocks are written to the sinks and its
+/// failed records retried in the same order as when it was captured, with
+/// the mapping values fetched then.
+async fn replay(
+    steps: Vec<ReplayStep>,
+    replayed_mappings: &ReplayedMappings,
+    rest_api: &RestApi,
+    module_name: &String,
+    end_block: u64,
+    sinks: Arc<Mutex<Sinks>>,
+    export_sink: Option<Arc<Mutex<ExportSink>>>,
+) {
+    info!("Replaying {} steps", steps.len());
+    for ReplayStep { step, mappings } in steps {
+        let unused = replayed_mappings.load(mappings);
+        if unused > 0 {
+            warn!(
+                "Replay diverged, {} captured mapping values weren't requested",
+                unused
+            );
+        }
 
-                            program_handler(&mut conn, rest_api, &records, &program_id);
+        match step {
+            Step::Block(data) => {
+                if data.clock.as_ref().map_or(0, |clock| clock.number) >= end_block {
+                    break;
+                }
+                let undo = data.step == ForkStep::StepUndo as i32;
+                index_block(
+                    data,
+                    undo,
+                    module_name,
+                    sinks.clone(),
+                    export_sink.clone(),
+                    None,
+                )
+                .await;
+            }
+            Step::Retry { transition_id } => {
+                let rest_api = rest_api.clone();
+                let retried =
+                    with_storage(move |conn| match conn.get_failed_record(&transition_id)? {
+                        Some(failed_record) => retry_failed_record(conn, &rest_api, &failed_record),
+                        None => {
+                            warn!(
+                                "Replay diverged, no failed record for transition {}",
+                                transition_id
+                            );
+                            Ok(false)
                         }
-                        None => {}
+                    })
+                    .await;
+                if let Err(err) = retried {
+                    warn!("Retry error {:#}", err);
+                }
+            }
+        }
+    }
+    info!("Replay done");
+}
+
+/// Keeps the head height metric up to date, so that the lag can be computed.
+async fn poll_head_height(rest_api: RestApi) {
+    loop {
+        let request_api = rest_api.clone();
+        let height = tokio::task::spawn_blocking(move || request_api.latest_height())
+            .await
+            .unwrap();
+        match height {
+            Ok(height) => {
+                metrics::set_head_height(height);
+                status::head_block_seen(height);
+            }
+            Err(err) => {
+                warn!("Head height error {:#}", err);
+                status::record_error(format!("head height: {:#}", err));
+            }
+        }
+        tokio::time::sleep(HEAD_POLL_INTERVAL).await;
+    }
+}
+
+/// Retries the failed records that are due, recording each to
+/// `capture_writer` first. Holding the sinks keeps the retries from
+/// interleaving with a block being written, so `rest_api` shouldn't back off.
+async fn retry_failed_records_loop(
+    rest_api: RestApi,
+    sinks: Arc<Mutex<Sinks>>,
+    capture_writer: Option<Arc<CaptureWriter>>,
+) {
+    loop {
+        tokio::time::sleep(RETRY_INTERVAL).await;
+
+        let rest_api = rest_api.clone();
+        let sinks = sinks.clone();
+        let capture_writer = capture_writer.clone();
+        let retried = tokio::task::spawn_blocking(move || {
+            let _sinks = sinks.lock().unwrap();
+            run_with_storage(|conn| {
+                let failed_records =
+                    conn.get_failed_records(Utc::now().timestamp(), RETRY_BATCH_SIZE, false)?;
+                let mut handled = 0;
+                for failed_record in failed_records.iter() {
+                    if let Some(capture_writer) = &capture_writer {
+                        capture_writer.retry(&failed_record.transition_id)?;
+                    }
+                    if retry_failed_record(conn, &rest_api, failed_record)? {
+                        handled += 1;
                     }
+                }
+                Ok((handled, failed_records.len()))
+            })
+        })
+        .await
+        .unwrap();
+        match retried {
+            Ok((_, 0)) => {}
+            Ok((handled, tried)) => info!("Retried {} failed records, {} handled", tried, handled),
+            Err(err) => {
+                warn!("Retry error {:#}", err);
+                status::record_error(format!("retry: {:#}", err));
+            }
+        }
+    }
+}
 
-                    // FIXME: Handling of the cursor is missing here. It should be saved each time
-                    // a full block has been correctly inserted in the database. By saving it
-                    // in the database, we ensure that if we crash, on startup we are going to
-                    // read it back from database and start back our SubstreamsStream with it
-                    // ensuring we are continuously streaming without ever losing a single
-                    // element.
+/// Serves `/metrics` alone, for `sync` running without `serve`.
+async fn serve_metrics(addr: String) {
+    let app = Router::new().route("/metrics", get(metrics::metrics_handler));
+    let addr = SocketAddr::from_str(&addr).unwrap();
+    info!("serving metrics on {}", addr);
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await
+        .unwrap();
+}
+
+fn open_sinks(args: &SinkArgs, rest_api: RestApi, program_id: String) -> Result<Sinks, Error> {
+    let mut sinks: Vec<Box<dyn Sink>> = Vec::new();
+    for kind in args.sinks.iter() {
+        match kind {
+            SinkKind::Database => sinks.push(Box::new(DatabaseSink::new(
+                rest_api.clone().without_backoff(),
+                program_id.clone(),
+            ))),
+            SinkKind::Ndjson => {
+                sinks.push(Box::new(NdjsonSink::open(args.ndjson_path.clone().into())?))
+            }
+            SinkKind::Kafka => sinks.push(Box::new(KafkaSink::open(
+                args.kafka_brokers.clone(),
+                args.kafka_topic.clone(),
+            )?)),
+        }
+    }
+    Sinks::open(sinks)
+}
+
+/// Verifies that the `previous_hash` of every indexed block is the hash of the
+/// block below it. Heights missing from the index are reported but can't be
+/// checked.
+async fn check_chain(start_block: i64, end_block: i64) -> Result<(), Error> {
+    let (breaks, gaps) = with_storage(move |conn| {
+        Ok((
+            conn.get_chain_breaks(start_block, end_block)?,
+            conn.get_chain_gaps(start_block, end_block)?,
+        ))
+    })
+    .await?;
+
+    for height in gaps.iter() {
+        warn!("Block {} is indexed without block {}", height, height - 1);
+    }
+    for chain_break in breaks.iter() {
+        error!(
+            "Block {} does not link to block {}: previous hash {}, expected {}",
+            chain_break.height,
+            chain_break.height - 1,
+            chain_break.previous_hash,
+            chain_break.parent_hash
+        );
+    }
+
+    if !breaks.is_empty() {
+        return Err(format_err!(
+            "{} blocks do not link to their parent",
+            breaks.len()
+        ));
+    }
+    info!("Chain is consistent, {} gaps", gaps.len());
+    Ok(())
+}
+
+/// Handles failed records again regardless of their next retry time, all of
+/// them but those given up on when no transition id is given. Returns whether
+/// they all succeeded.
+async fn retry(rest_api: RestApi, transition_ids: Vec<String>) -> Result<bool, Error> {
+    let (handled, tried) = with_storage(move |conn| {
+        if transition_ids.is_empty() {
+            return retry_failed_records(conn, &rest_api, i64::MAX, i64::MAX);
+        }
+
+        let mut handled = 0;
+        for transition_id in transition_ids.iter() {
+            match conn.get_failed_record(transition_id)? {
+                Some(failed_record) => {
+                    if retry_failed_record(conn, &rest_api, &failed_record)? {
+                        handled += 1;
+                    }
                 }
-            },
+                None => warn!("No failed record for transition {}", transition_id),
+            }
         }
+        Ok((handled, transition_ids.len()))
+    })
+    .await?;
+
+    info!("Retried {} failed records, {} handled", tried, handled);
+    Ok(handled == tried)
+}
+
+async fn list_failures(limit: i64) -> Result<(), Error> {
+    let failed_records =
+        with_storage(move |conn| conn.get_failed_records(i64::MAX, limit, true)).await?;
+
+    for failed_record in failed_records.iter() {
+        let next_retry = if failed_record.gave_up {
+            "gave up".to_string()
+        } else {
+            format!(
+                "next retry {}",
+                NaiveDateTime::from_timestamp_opt(failed_record.next_retry_at, 0)
+                    .map_or(failed_record.next_retry_at.to_string(), |time| time
+                        .to_string())
+            )
+        };
+        println!(
+            "{} {} height {} attempts {} {}: {}",
+            failed_record.transition_id,
+            failed_record.function,
+            failed_record.height,
+            failed_record.attempts,
+            next_retry,
+            failed_record.error
+        );
     }
+    Ok(())
+}
+
+async fn export(
+    tables: Vec<String>,
+    start_block: i64,
+    end_block: i64,
+    options: ExportOptions,
+) -> Result<(), Error> {
+    tokio::task::spawn_blocking(move || {
+        for table in tables.iter() {
+            let files = export_table(table, start_block, end_block, &options)?;
+            info!("Exported {} to {} files", table, files.len());
+        }
+        Ok(())
+    })
+    .await?
 }
 
-async fn serve(rest_api: &String, host: &String, port: &u16) {
+/// `listen` forwards events published through Postgres by a `sync` running in
+/// another process. In `all` mode events are delivered in-process instead.
+async fn serve(rest_api: &RestApi, host: &String, port: &u16, listen: bool) {
+    if listen {
+        tokio::task::spawn_blocking(|| loop {
+            if let Err(err) = listen_notifications() {
+                warn!("Listen notifications error {:#}", err);
+            }
+            std::thread::sleep(Duration::from_secs(5));
+        });
+    }
+
     let app = routes().layer(
         CorsLayer::new()
-            .allow_methods([Method::GET])
+            .allow_methods([Method::GET, Method::POST, Method::PUT])
             .allow_origin(Any)
             .allow_headers(Any),
     );
     let addr = SocketAddr::from_str(&format!("{}:{}", host, port)).unwrap();
-    println!("listening on {}", addr);
+    info!("listening on {}", addr);
     axum::Server::bind(&addr)
         .serve(app.into_make_service())
         .await
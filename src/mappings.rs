@@ -1,15 +1,27 @@
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use snarkvm::prelude::{Ciphertext, Entry, Owner, Plaintext, Record, Testnet3};
 use std::collections::HashMap;
 
-fn revert_to_str_map(value: &String) -> Result<HashMap<String, String>, Error> {
+/// Fields of a struct mapping value.
+struct Fields(HashMap<String, String>);
+
+impl Fields {
+    fn get(&self, name: &str) -> Result<&String, Error> {
+        self.0
+            .get(name)
+            .ok_or_else(|| anyhow!("Mapping value has no field {}", name))
+    }
+}
+
+fn revert_to_str_map(value: &String) -> Result<Fields, Error> {
     let json_str = Regex::new(r#"(\w+)"#)
         .unwrap()
         .replace_all(value.replace(r"\n", "").trim_matches('"'), r#""$1""#)
         .to_string();
     let mapping = serde_json::from_str(&json_str)?;
-    Ok(mapping)
+    Ok(Fields(mapping))
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -32,18 +44,27 @@ impl Proposal {
     pub fn from_mapping_value(value: &String) -> Result<Self, Error> {
         let data = revert_to_str_map(value)?;
         Ok(Self {
-            id: data["id"].trim_end_matches("u64").parse::<u64>()?,
-            title: data["title"].trim_end_matches("field").to_string(),
-            proposer: data["proposer"].clone(),
-            summary: data["summary"].trim_end_matches("field").to_string(),
-            body: data["body"].trim_end_matches("field").to_string(),
-            dao_id: data["dao_id"].trim_end_matches("u64").parse::<u64>()?,
-            created: data["created"].trim_end_matches("u32").parse::<u32>()?,
-            duration: data["duration"].trim_end_matches("u32").parse::<u32>()?,
-            proposal_type: data["proposal_type"].trim_end_matches("u8").parse::<u8>()?,
-            adopt: data["adopt"].trim_end_matches("u64").parse::<u64>()?,
-            reject: data["reject"].trim_end_matches("u64").parse::<u64>()?,
-            status: data["status"].trim_end_matches("u8").parse::<u8>()?,
+            id: data.get("id")?.trim_end_matches("u64").parse::<u64>()?,
+            title: data.get("title")?.trim_end_matches("field").to_string(),
+            proposer: data.get("proposer")?.clone(),
+            summary: data.get("summary")?.trim_end_matches("field").to_string(),
+            body: data.get("body")?.trim_end_matches("field").to_string(),
+            dao_id: data.get("dao_id")?.trim_end_matches("u64").parse::<u64>()?,
+            created: data
+                .get("created")?
+                .trim_end_matches("u32")
+                .parse::<u32>()?,
+            duration: data
+                .get("duration")?
+                .trim_end_matches("u32")
+                .parse::<u32>()?,
+            proposal_type: data
+                .get("proposal_type")?
+                .trim_end_matches("u8")
+                .parse::<u8>()?,
+            adopt: data.get("adopt")?.trim_end_matches("u64").parse::<u64>()?,
+            reject: data.get("reject")?.trim_end_matches("u64").parse::<u64>()?,
+            status: data.get("status")?.trim_end_matches("u8").parse::<u8>()?,
         })
     }
 }
@@ -59,9 +80,9 @@ impl Profile {
     pub fn from_mapping_value(value: &String) -> Result<Self, Error> {
         let data = revert_to_str_map(value)?;
         Ok(Self {
-            name: data["name"].trim_end_matches("field").to_string(),
-            avatar: data["avatar"].trim_end_matches("field").to_string(),
-            bio: data["bio"].trim_end_matches("field").to_string(),
+            name: data.get("name")?.trim_end_matches("field").to_string(),
+            avatar: data.get("avatar")?.trim_end_matches("field").to_string(),
+            bio: data.get("bio")?.trim_end_matches("field").to_string(),
         })
     }
 }
@@ -87,27 +108,41 @@ impl Dao {
     pub fn from_mapping_value(value: &String) -> Result<Self, Error> {
         let data = revert_to_str_map(value)?;
         Ok(Self {
-            id: data["id"].trim_end_matches("u64").parse::<u64>()?,
-            name: data["name"].trim_end_matches("field").to_string(),
-            dao_type: data["dao_type"].trim_end_matches("u8").parse::<u8>()?,
-            creator: data["creator"].to_string(),
-            token_info_id: data["token_info_id"]
+            id: data.get("id")?.trim_end_matches("u64").parse::<u64>()?,
+            name: data.get("name")?.trim_end_matches("field").to_string(),
+            dao_type: data.get("dao_type")?.trim_end_matches("u8").parse::<u8>()?,
+            creator: data.get("creator")?.to_string(),
+            token_info_id: data
+                .get("token_info_id")?
+                .trim_end_matches("u64")
+                .parse::<u64>()?,
+            icon: data.get("icon")?.trim_end_matches("field").to_string(),
+            description: data
+                .get("description")?
+                .trim_end_matches("field")
+                .to_string(),
+            official_link: data
+                .get("official_link")?
+                .trim_end_matches("field")
+                .to_string(),
+            proposal_count: data
+                .get("proposal_count")?
                 .trim_end_matches("u64")
                 .parse::<u64>()?,
-            icon: data["icon"].trim_end_matches("field").to_string(),
-            description: data["description"].trim_end_matches("field").to_string(),
-            official_link: data["official_link"].trim_end_matches("field").to_string(),
-            proposal_count: data["proposal_count"]
+            pass_proposal_count: data
+                .get("pass_proposal_count")?
                 .trim_end_matches("u64")
                 .parse::<u64>()?,
-            pass_proposal_count: data["pass_proposal_count"]
+            vote_count: data
+                .get("vote_count")?
                 .trim_end_matches("u64")
                 .parse::<u64>()?,
-            vote_count: data["vote_count"].trim_end_matches("u64").parse::<u64>()?,
-            passed_votes_proportion: data["passed_votes_proportion"]
+            passed_votes_proportion: data
+                .get("passed_votes_proportion")?
                 .trim_end_matches("u64")
                 .parse::<u64>()?,
-            passed_tokens_proportion: data["passed_tokens_proportion"]
+            passed_tokens_proportion: data
+                .get("passed_tokens_proportion")?
                 .trim_end_matches("u64")
                 .parse::<u64>()?,
         })
@@ -126,16 +161,68 @@ pub struct Token {
 
 impl Token {
     pub fn from_mapping_value(value: &String) -> Result<Self, Error> {
-        let data = revert_to_str_map(value)?;
+        Self::from_fields(&revert_to_str_map(value)?)
+    }
+
+    /// The token of a `Token` record output, `None` for another record or
+    /// one whose owner or fields are private.
+    pub fn from_record(
+        record: &Record<Testnet3, Ciphertext<Testnet3>>,
+    ) -> Result<Option<Self>, Error> {
+        let mut fields = HashMap::new();
+        match record.owner() {
+            Owner::Public(owner) => fields.insert("owner".to_string(), owner.to_string()),
+            Owner::Private(_) => return Ok(None),
+        };
+        for (name, entry) in record.data() {
+            match entry {
+                Entry::Constant(value) | Entry::Public(value) => {
+                    fields.insert(name.to_string(), value.to_string())
+                }
+                Entry::Private(_) => return Ok(None),
+            };
+        }
+        Self::from_record_fields(fields)
+    }
+
+    /// The token of a decrypted `Token` record, `None` for another record.
+    pub fn from_plaintext_record(
+        record: &Record<Testnet3, Plaintext<Testnet3>>,
+    ) -> Result<Option<Self>, Error> {
+        let mut fields = HashMap::new();
+        fields.insert("owner".to_string(), (**record.owner()).to_string());
+        for (name, entry) in record.data() {
+            let (Entry::Constant(value) | Entry::Public(value) | Entry::Private(value)) = entry;
+            fields.insert(name.to_string(), value.to_string());
+        }
+        Self::from_record_fields(fields)
+    }
+
+    fn from_record_fields(fields: HashMap<String, String>) -> Result<Option<Self>, Error> {
+        let names = ["gates", "token_info_id", "amount", "expires", "staked_at"];
+        if fields.len() != names.len() + 1 || !names.iter().all(|name| fields.contains_key(*name)) {
+            return Ok(None);
+        }
+        Self::from_fields(&Fields(fields)).map(Some)
+    }
+
+    fn from_fields(data: &Fields) -> Result<Self, Error> {
         Ok(Self {
-            owner: data["owner"].to_string(),
-            gates: data["gates"].trim_end_matches("u64").parse::<u64>()?,
-            token_info_id: data["token_info_id"]
+            owner: data.get("owner")?.to_string(),
+            gates: data.get("gates")?.trim_end_matches("u64").parse::<u64>()?,
+            token_info_id: data
+                .get("token_info_id")?
+                .trim_end_matches("u64")
+                .parse::<u64>()?,
+            amount: data.get("amount")?.trim_end_matches("u64").parse::<u64>()?,
+            expires: data
+                .get("expires")?
+                .trim_end_matches("u32")
+                .parse::<u32>()?,
+            staked_at: data
+                .get("staked_at")?
                 .trim_end_matches("u64")
                 .parse::<u64>()?,
-            amount: data["amount"].trim_end_matches("u64").parse::<u64>()?,
-            expires: data["expires"].trim_end_matches("u32").parse::<u32>()?,
-            staked_at: data["staked_at"].trim_end_matches("u64").parse::<u64>()?,
         })
     }
 }
@@ -151,9 +238,10 @@ impl HoldToken {
     pub fn from_mapping_value(value: &String) -> Result<Self, Error> {
         let data = revert_to_str_map(value)?;
         Ok(Self {
-            token_owner: data["token_owner"].to_string(),
-            amount: data["amount"].trim_end_matches("u64").parse::<u64>()?,
-            token_info_id: data["token_info_id"]
+            token_owner: data.get("token_owner")?.to_string(),
+            amount: data.get("amount")?.trim_end_matches("u64").parse::<u64>()?,
+            token_info_id: data
+                .get("token_info_id")?
                 .trim_end_matches("u64")
                 .parse::<u64>()?,
         })
@@ -177,19 +265,21 @@ impl TokenInfo {
     pub fn from_mapping_value(value: &String) -> Result<Self, Error> {
         let data = revert_to_str_map(value)?;
         Ok(Self {
-            id: data["id"].trim_end_matches("u64").parse::<u64>()?,
-            name: data["name"].trim_end_matches("field").to_string(),
-            symbol: data["symbol"].trim_end_matches("field").to_string(),
-            supply: data["supply"].trim_end_matches("u64").parse::<u64>()?,
-            decimals: data["decimals"].trim_end_matches("u8").parse::<u8>()?,
-            max_mint_amount: data["max_mint_amount"]
+            id: data.get("id")?.trim_end_matches("u64").parse::<u64>()?,
+            name: data.get("name")?.trim_end_matches("field").to_string(),
+            symbol: data.get("symbol")?.trim_end_matches("field").to_string(),
+            supply: data.get("supply")?.trim_end_matches("u64").parse::<u64>()?,
+            decimals: data.get("decimals")?.trim_end_matches("u8").parse::<u8>()?,
+            max_mint_amount: data
+                .get("max_mint_amount")?
                 .trim_end_matches("u64")
                 .parse::<u64>()?,
-            minted_amount: data["minted_amount"]
+            minted_amount: data
+                .get("minted_amount")?
                 .trim_end_matches("u64")
                 .parse::<u64>()?,
-            dao_id: data["dao_id"].trim_end_matches("u64").parse::<u64>()?,
-            only_creator_can_mint: data["only_creator_can_mint"].parse::<bool>()?,
+            dao_id: data.get("dao_id")?.trim_end_matches("u64").parse::<u64>()?,
+            only_creator_can_mint: data.get("only_creator_can_mint")?.parse::<bool>()?,
         })
     }
 }
@@ -207,11 +297,14 @@ impl Vote {
     pub fn from_mapping_value(value: &String) -> Result<Self, Error> {
         let data = revert_to_str_map(value)?;
         Ok(Self {
-            voter: data["voter"].trim_end_matches("field").to_string(),
-            proposal_id: data["proposal_id"].trim_end_matches("u64").parse::<u64>()?,
-            is_agreed: data["is_agreed"].parse::<bool>()?,
-            time: data["time"].trim_end_matches("u32").parse::<u32>()?,
-            amount: data["amount"].trim_end_matches("u64").parse::<u64>()?,
+            voter: data.get("voter")?.trim_end_matches("field").to_string(),
+            proposal_id: data
+                .get("proposal_id")?
+                .trim_end_matches("u64")
+                .parse::<u64>()?,
+            is_agreed: data.get("is_agreed")?.parse::<bool>()?,
+            time: data.get("time")?.trim_end_matches("u32").parse::<u32>()?,
+            amount: data.get("amount")?.trim_end_matches("u64").parse::<u64>()?,
         })
     }
 }
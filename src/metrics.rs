@@ -0,0 +1,147 @@
+use axum::{
+    extract::MatchedPath,
+    http::{header::CONTENT_TYPE, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    Encoder, HistogramVec, IntCounter, IntCounterVec, IntGauge, TextEncoder,
+};
+use std::time::Instant;
+
+lazy_static! {
+    pub static ref HEAD_HEIGHT: IntGauge = register_int_gauge!(
+        "indexer_head_block_height",
+        "Latest block height of the Aleo REST API"
+    )
+    .unwrap();
+    pub static ref INDEXED_HEIGHT: IntGauge = register_int_gauge!(
+        "indexer_indexed_block_height",
+        "Height of the last block written to the sinks"
+    )
+    .unwrap();
+    pub static ref BLOCK_LAG: IntGauge = register_int_gauge!(
+        "indexer_block_lag",
+        "Blocks between the head and the last indexed block"
+    )
+    .unwrap();
+    pub static ref BLOCKS_PROCESSED: IntCounter = register_int_counter!(
+        "indexer_blocks_processed_total",
+        "Blocks written to the sinks"
+    )
+    .unwrap();
+    pub static ref RECORDS_PROCESSED: IntCounter = register_int_counter!(
+        "indexer_records_processed_total",
+        "Records written to the sinks"
+    )
+    .unwrap();
+    pub static ref FETCH_MAPPING_DURATION: HistogramVec = register_histogram_vec!(
+        "indexer_fetch_mapping_duration_seconds",
+        "Latency of mapping value requests to the Aleo REST API",
+        &["mapping"]
+    )
+    .unwrap();
+    pub static ref FETCH_MAPPING_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "indexer_fetch_mapping_errors_total",
+        "Failed mapping value requests to the Aleo REST API",
+        &["mapping"]
+    )
+    .unwrap();
+    pub static ref FETCH_MAPPING_NULLS: IntCounterVec = register_int_counter_vec!(
+        "indexer_fetch_mapping_null_total",
+        "Mapping value requests to the Aleo REST API answered with null",
+        &["mapping"]
+    )
+    .unwrap();
+    pub static ref REST_API_RETRIES: IntCounter = register_int_counter!(
+        "indexer_rest_api_retries_total",
+        "Aleo REST API requests sent again after a transient error"
+    )
+    .unwrap();
+    pub static ref DB_WRITE_DURATION: HistogramVec = register_histogram_vec!(
+        "indexer_db_write_duration_seconds",
+        "Latency of writing a block to the database",
+        &["operation"]
+    )
+    .unwrap();
+    pub static ref STREAM_RECONNECTS: IntCounter = register_int_counter!(
+        "indexer_stream_reconnects_total",
+        "Substreams reconnections after a connection or stream error"
+    )
+    .unwrap();
+    pub static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "indexer_http_request_duration_seconds",
+        "Latency of API requests",
+        &["method", "route", "status"]
+    )
+    .unwrap();
+}
+
+/// Registers the metrics up front, so that they are exported before their
+/// first update.
+pub fn register() {
+    lazy_static::initialize(&HEAD_HEIGHT);
+    lazy_static::initialize(&INDEXED_HEIGHT);
+    lazy_static::initialize(&BLOCK_LAG);
+    lazy_static::initialize(&BLOCKS_PROCESSED);
+    lazy_static::initialize(&RECORDS_PROCESSED);
+    lazy_static::initialize(&FETCH_MAPPING_DURATION);
+    lazy_static::initialize(&FETCH_MAPPING_ERRORS);
+    lazy_static::initialize(&FETCH_MAPPING_NULLS);
+    lazy_static::initialize(&REST_API_RETRIES);
+    lazy_static::initialize(&DB_WRITE_DURATION);
+    lazy_static::initialize(&STREAM_RECONNECTS);
+    lazy_static::initialize(&HTTP_REQUEST_DURATION);
+}
+
+pub fn set_head_height(height: i64) {
+    HEAD_HEIGHT.set(height);
+    BLOCK_LAG.set(height - INDEXED_HEIGHT.get());
+}
+
+pub fn block_indexed(height: i64, record_count: usize) {
+    INDEXED_HEIGHT.set(height);
+    BLOCKS_PROCESSED.inc();
+    RECORDS_PROCESSED.inc_by(record_count as u64);
+    // The head is only polled, don't report a negative lag in between.
+    BLOCK_LAG.set((HEAD_HEIGHT.get() - height).max(0));
+}
+
+/// Records the latency of requests to matched routes, labelled with the
+/// route pattern rather than the path so that ids don't explode the series.
+pub async fn track_http<B>(request: Request<B>, next: Next<B>) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .observe(start.elapsed().as_secs_f64());
+
+    response
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "operations",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", content_type = "text/plain; version=0.0.4", body = String),
+    )
+)]
+pub async fn metrics_handler() -> Response {
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    if let Err(err) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
+    }
+
+    ([(CONTENT_TYPE, encoder.format_type().to_string())], buffer).into_response()
+}
@@ -1,25 +1,158 @@
 use super::schema::auto_increment;
 use super::schema::balances;
+use super::schema::blocks;
+use super::schema::cursors;
 use super::schema::daos;
 use super::schema::extend_pledge_period;
+use super::schema::failed_records;
 use super::schema::profiles;
 use super::schema::proposals;
 use super::schema::record;
+use super::schema::record_inputs;
+use super::schema::record_outputs;
 use super::schema::stake_amounts;
 use super::schema::token;
 use super::schema::token_infos;
+use super::schema::transactions;
+use super::schema::view_keys;
 use super::schema::votes;
+use crate::amount::Amount;
+use async_graphql::SimpleObject;
 use diesel::{prelude::*, Queryable};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Queryable, Selectable, Deserialize, Serialize)]
+/// The last block a sink has written, with the Substreams cursor to resume
+/// streaming after it.
+#[derive(Queryable, Selectable, QueryableByName, Clone, Debug, Deserialize, Serialize)]
+#[diesel(table_name = cursors)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Cursors {
+    pub name: String,
+    pub cursor: String,
+    pub height: i64,
+    pub block_hash: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = cursors)]
+pub struct NewCursors<'a> {
+    pub name: &'a str,
+    pub cursor: &'a str,
+    pub height: i64,
+    pub block_hash: &'a str,
+}
+
+/// A transition whose handler failed, `record` being the protobuf-encoded
+/// `proto::Record` to handle it again.
+#[derive(Queryable, Selectable, QueryableByName, Clone, Debug)]
+#[diesel(table_name = failed_records)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct FailedRecords {
+    pub transition_id: String,
+    pub program: String,
+    pub function: String,
+    pub height: i64,
+    pub record: Vec<u8>,
+    pub error: String,
+    pub attempts: i64,
+    pub first_failed_at: i64,
+    pub last_failed_at: i64,
+    pub next_retry_at: i64,
+    /// Whether `sync` stopped retrying the record.
+    pub gave_up: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = failed_records)]
+pub struct NewFailedRecords<'a> {
+    pub transition_id: &'a str,
+    pub program: &'a str,
+    pub function: &'a str,
+    pub height: i64,
+    pub record: &'a [u8],
+    pub error: &'a str,
+    pub attempts: i64,
+    pub first_failed_at: i64,
+    pub last_failed_at: i64,
+    pub next_retry_at: i64,
+    pub gave_up: bool,
+}
+
+#[derive(Queryable, Selectable, Clone, Deserialize, Serialize, SimpleObject, ToSchema)]
+#[diesel(table_name = blocks)]
+#[graphql(name = "Block", complex)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Blocks {
+    pub height: i64,
+    pub block_hash: String,
+    pub previous_hash: Option<String>,
+    pub timestamp: i64,
+    pub transaction_count: i64,
+    pub transition_count: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = blocks)]
+pub struct NewBlocks<'a> {
+    pub height: i64,
+    pub block_hash: &'a str,
+    pub previous_hash: Option<&'a str>,
+    pub timestamp: i64,
+    pub transaction_count: i64,
+    pub transition_count: i64,
+}
+
+#[derive(Queryable, Selectable, Clone, Deserialize, Serialize, SimpleObject, ToSchema)]
+#[diesel(table_name = transactions)]
+#[graphql(name = "Transaction")]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Transactions {
+    pub transaction_id: String,
+    pub height: i64,
+    pub transition_count: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = transactions)]
+pub struct NewTransactions<'a> {
+    pub transaction_id: &'a str,
+    pub height: i64,
+    pub transition_count: i64,
+}
+
+/// A block whose `previous_hash` doesn't match the hash of the block indexed
+/// at the height below.
+#[derive(QueryableByName, Clone, Debug, Serialize)]
+pub struct ChainBreak {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub height: i64,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub previous_hash: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub parent_hash: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RespBlock {
+    #[serde(flatten)]
+    pub block: Blocks,
+    pub transactions: Vec<Transactions>,
+}
+
+#[derive(Queryable, Selectable, Clone, Deserialize, Serialize, SimpleObject, ToSchema)]
 #[diesel(table_name = record)]
+#[graphql(name = "Record", complex)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Record {
     pub program: String,
     pub function: String,
-    pub inputs: String,
-    pub outputs: String,
+    #[graphql(skip)]
+    #[schema(value_type = Vec<Input>)]
+    pub inputs: serde_json::Value,
+    #[graphql(skip)]
+    #[schema(value_type = Vec<Output>)]
+    pub outputs: serde_json::Value,
     pub block_hash: String,
     pub previous_hash: String,
     pub transaction_id: String,
@@ -34,8 +167,8 @@ pub struct Record {
 pub struct NewRecord<'a> {
     pub program: &'a str,
     pub function: &'a str,
-    pub inputs: &'a str,
-    pub outputs: &'a str,
+    pub inputs: serde_json::Value,
+    pub outputs: serde_json::Value,
     pub block_hash: &'a str,
     pub previous_hash: &'a str,
     pub transaction_id: &'a str,
@@ -45,23 +178,62 @@ pub struct NewRecord<'a> {
     pub timestamp: i64,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, SimpleObject, ToSchema)]
 pub struct Input {
+    #[graphql(name = "type")]
     pub r#type: String,
     pub id: String,
     pub value: String,
     pub tag: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, SimpleObject, ToSchema)]
 pub struct Output {
+    #[graphql(name = "type")]
     pub r#type: String,
     pub id: String,
     pub checksum: String,
     pub value: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Insertable)]
+#[diesel(table_name = record_inputs)]
+pub struct NewRecordInput<'a> {
+    pub transition_id: &'a str,
+    pub position: i64,
+    #[diesel(column_name = type_)]
+    pub r#type: &'a str,
+    pub id: &'a str,
+    pub value: &'a str,
+    pub tag: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = record_outputs)]
+pub struct NewRecordOutput<'a> {
+    pub transition_id: &'a str,
+    pub position: i64,
+    #[diesel(column_name = type_)]
+    pub r#type: &'a str,
+    pub id: &'a str,
+    pub checksum: &'a str,
+    pub value: &'a str,
+}
+
+/// Each given field narrows records down to the transitions with an input or
+/// output matching it.
+#[derive(Clone, Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RecordFilter {
+    /// Input or output id, e.g. a record serial number or commitment.
+    pub id: Option<String>,
+    /// Input or output value, e.g. a record ciphertext.
+    pub value: Option<String>,
+    /// Input or output type, e.g. `record` or `public`.
+    pub r#type: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct RespRecords {
     pub records: Vec<String>,
     pub transaction_id: String,
@@ -73,14 +245,21 @@ pub struct RespRecords {
     pub outputs: Vec<Output>,
 }
 
-#[derive(Queryable, Selectable, Deserialize, Serialize)]
+#[derive(Queryable, Selectable, Clone, Deserialize, Serialize, SimpleObject, ToSchema)]
 #[diesel(table_name = profiles)]
+#[graphql(name = "Profile", complex)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Profiles {
     pub address: String,
     pub name: String,
     pub avatar: String,
     pub bio: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_height: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_by_transition_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_timestamp: Option<i64>,
 }
 
 #[derive(Insertable)]
@@ -90,6 +269,9 @@ pub struct NewProfiles<'a> {
     pub name: &'a str,
     pub avatar: &'a str,
     pub bio: &'a str,
+    pub updated_at_height: Option<i64>,
+    pub updated_by_transition_id: Option<&'a str>,
+    pub updated_at_timestamp: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -100,6 +282,88 @@ pub struct RespProfile {
     pub bio: String,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TokenInfoTotal {
+    pub token_info_id: i64,
+    pub total: Amount,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DaoTotal {
+    pub dao_id: i64,
+    pub total: Amount,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RespTotal {
+    pub total: Amount,
+    pub by_token_info: Vec<TokenInfoTotal>,
+    pub by_dao: Vec<DaoTotal>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RespStats {
+    pub pledgers_total: RespTotal,
+    pub stake_funds_total: RespTotal,
+    pub funds_total: RespTotal,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RespTokenPledgers {
+    pub token_info_id: i64,
+    pub pledgers: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RespProposalVotes {
+    pub proposal_id: i64,
+    pub totals: VoteTotals,
+    pub votes: Vec<Votes>,
+}
+
+/// A vote of an account together with the proposal and DAO it belongs to.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RespVoterVote {
+    #[serde(flatten)]
+    pub vote: Votes,
+    pub proposal: Option<Proposals>,
+    pub dao: Option<Daos>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RespError {
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ReqViewKey {
+    /// Aleo view key, `AViewKey1...`
+    pub view_key: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RespViewKey {
+    pub address: String,
+    /// Bearer token for the private endpoints of the address, only given
+    /// once. Registering the view key again replaces it.
+    pub access_token: String,
+}
+
+/// Unspent token records of an address summed by token.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RespTokenHolding {
+    pub token_info_id: i64,
+    pub amount: Amount,
+    pub records: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ReqProfile {
+    pub name: String,
+    pub avatar: String,
+    pub bio: String,
+}
+
 // #[derive(Queryable, Selectable, Deserialize, Serialize)]
 // #[diesel(table_name = daos_schema)]
 // #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -112,19 +376,26 @@ pub struct RespProfile {
 //     pub official_link: String,
 // }
 
-#[derive(Queryable, Selectable, Deserialize, Serialize)]
+#[derive(Queryable, Selectable, Clone, Deserialize, Serialize, SimpleObject, ToSchema)]
 #[diesel(table_name = token_infos)]
+#[graphql(name = "TokenInfo", complex)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct TokenInfos {
     pub id: i64,
     pub name: String,
     pub symbol: String,
-    pub supply: i64,
+    pub supply: Amount,
     pub decimals: i64,
-    pub max_mint_amount: i64,
-    pub minted_amount: i64,
+    pub max_mint_amount: Amount,
+    pub minted_amount: Amount,
     pub dao_id: i64,
     pub only_creator_can_mint: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_height: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_by_transition_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_timestamp: Option<i64>,
 }
 
 #[derive(Insertable)]
@@ -133,16 +404,20 @@ pub struct NewTokenInfos<'a> {
     pub id: i64,
     pub name: &'a str,
     pub symbol: &'a str,
-    pub supply: i64,
+    pub supply: Amount,
     pub decimals: i64,
-    pub max_mint_amount: i64,
-    pub minted_amount: i64,
+    pub max_mint_amount: Amount,
+    pub minted_amount: Amount,
     pub dao_id: i64,
     pub only_creator_can_mint: bool,
+    pub updated_at_height: Option<i64>,
+    pub updated_by_transition_id: Option<&'a str>,
+    pub updated_at_timestamp: Option<i64>,
 }
 
-#[derive(Queryable, Selectable, Deserialize, Serialize)]
+#[derive(Queryable, Selectable, Clone, Deserialize, Serialize, SimpleObject, ToSchema)]
 #[diesel(table_name = daos)]
+#[graphql(name = "Dao", complex)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Daos {
     pub id: i64,
@@ -153,11 +428,17 @@ pub struct Daos {
     pub icon: String,
     pub description: String,
     pub official_link: String,
-    pub proposal_count: i64,
-    pub pass_proposal_count: i64,
-    pub vote_count: i64,
-    pub passed_votes_proportion: i64,
-    pub passed_tokens_proportion: i64,
+    pub proposal_count: Amount,
+    pub pass_proposal_count: Amount,
+    pub vote_count: Amount,
+    pub passed_votes_proportion: Amount,
+    pub passed_tokens_proportion: Amount,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_height: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_by_transition_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_timestamp: Option<i64>,
 }
 
 #[derive(Insertable)]
@@ -171,38 +452,110 @@ pub struct NewDaos<'a> {
     pub icon: &'a str,
     pub description: &'a str,
     pub official_link: &'a str,
-    pub proposal_count: i64,
-    pub pass_proposal_count: i64,
-    pub vote_count: i64,
-    pub passed_votes_proportion: i64,
-    pub passed_tokens_proportion: i64,
+    pub proposal_count: Amount,
+    pub pass_proposal_count: Amount,
+    pub vote_count: Amount,
+    pub passed_votes_proportion: Amount,
+    pub passed_tokens_proportion: Amount,
+    pub updated_at_height: Option<i64>,
+    pub updated_by_transition_id: Option<&'a str>,
+    pub updated_at_timestamp: Option<i64>,
 }
 
-#[derive(Queryable, Selectable, Deserialize, Serialize)]
+/// A token record output by the program, `tag` being set once computed
+/// with the owner's view key and the `spent_*` columns once an input with
+/// that tag is seen. `private` records are only known for registered view
+/// keys.
+#[derive(Queryable, Selectable, Clone, Debug, Deserialize, Serialize, ToSchema)]
 #[diesel(table_name = token)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Token {
+    pub commitment: String,
     pub owner: String,
-    pub gates: i64,
+    pub gates: Amount,
     pub token_info_id: i64,
-    pub amount: i64,
+    pub amount: Amount,
     pub expires: i64,
     pub staked_at: i64,
+    pub function: String,
+    pub created_at_height: i64,
+    pub created_by_transition_id: String,
+    pub created_at_timestamp: i64,
+    pub tag: Option<String>,
+    pub serial_number: Option<String>,
+    pub spent_at_height: Option<i64>,
+    pub spent_by_transition_id: Option<String>,
+    pub private: bool,
 }
 
 #[derive(Insertable)]
 #[diesel(table_name = token)]
 pub struct NewToken<'a> {
+    pub commitment: &'a str,
     pub owner: &'a str,
-    pub gates: i64,
+    pub gates: Amount,
     pub token_info_id: i64,
-    pub amount: i64,
+    pub amount: Amount,
     pub expires: i64,
     pub staked_at: i64,
+    pub function: &'a str,
+    pub created_at_height: i64,
+    pub created_by_transition_id: &'a str,
+    pub created_at_timestamp: i64,
+    pub tag: Option<&'a str>,
+    pub serial_number: Option<&'a str>,
+    pub spent_at_height: Option<i64>,
+    pub spent_by_transition_id: Option<&'a str>,
+    pub private: bool,
 }
 
-#[derive(Queryable, Selectable, Deserialize, Serialize)]
+/// A view key registered by its owner, see `view_keys`. Never serialized.
+#[derive(Queryable, Selectable, QueryableByName, Clone)]
+#[diesel(table_name = view_keys)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ViewKeys {
+    pub address: String,
+    pub encrypted_view_key: Vec<u8>,
+    pub access_token_hash: String,
+    pub registered_at: i64,
+    pub backfilled_at_height: Option<i64>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = view_keys)]
+pub struct NewViewKeys<'a> {
+    pub address: &'a str,
+    pub encrypted_view_key: &'a [u8],
+    pub access_token_hash: &'a str,
+    pub registered_at: i64,
+    pub backfilled_at_height: Option<i64>,
+}
+
+/// A `record` output of a transition of the program, as needed to index a
+/// token record synced before its owner registered a view key.
+#[derive(Clone, Debug)]
+pub struct StoredRecordOutput {
+    pub transition_id: String,
+    pub function: String,
+    pub height: i64,
+    pub timestamp: i64,
+    pub commitment: String,
+    pub value: String,
+}
+
+/// The input consuming a record, found by the tag of the record.
+#[derive(Clone, Debug)]
+pub struct SpendingInput {
+    pub serial_number: String,
+    pub transition_id: String,
+    pub height: i64,
+}
+
+#[derive(
+    Queryable, Selectable, QueryableByName, Clone, Deserialize, Serialize, SimpleObject, ToSchema,
+)]
 #[diesel(table_name = proposals)]
+#[graphql(name = "Proposal", complex)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Proposals {
     pub id: i64,
@@ -213,10 +566,17 @@ pub struct Proposals {
     pub dao_id: i64,
     pub created: i64,
     pub duration: i64,
+    #[graphql(name = "type")]
     pub type_: i64,
-    pub adopt: i64,
-    pub reject: i64,
+    pub adopt: Amount,
+    pub reject: Amount,
     pub status: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_height: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_by_transition_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_timestamp: Option<i64>,
 }
 
 #[derive(Insertable)]
@@ -231,13 +591,19 @@ pub struct NewProposals<'a> {
     pub created: i64,
     pub duration: i64,
     pub type_: i64,
-    pub adopt: i64,
-    pub reject: i64,
+    pub adopt: Amount,
+    pub reject: Amount,
     pub status: i64,
+    pub updated_at_height: Option<i64>,
+    pub updated_by_transition_id: Option<&'a str>,
+    pub updated_at_timestamp: Option<i64>,
 }
 
-#[derive(Queryable, Selectable, Deserialize, Serialize)]
+#[derive(
+    Queryable, Selectable, QueryableByName, Clone, Deserialize, Serialize, SimpleObject, ToSchema,
+)]
 #[diesel(table_name = votes)]
+#[graphql(name = "Vote", complex)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Votes {
     pub key: String,
@@ -245,7 +611,28 @@ pub struct Votes {
     pub proposal_id: i64,
     pub is_agreed: bool,
     pub time: i64,
-    pub amount: i64,
+    pub amount: Amount,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_height: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_by_transition_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_timestamp: Option<i64>,
+}
+
+#[derive(QueryableByName, Clone, Default, Deserialize, Serialize, SimpleObject, ToSchema)]
+#[graphql(name = "VoteTotals")]
+pub struct VoteTotals {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub agree_count: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub reject_count: i64,
+    /// Token amount backing the agreeing votes
+    #[diesel(sql_type = diesel::sql_types::Numeric)]
+    pub agree_amount: Amount,
+    /// Token amount backing the rejecting votes
+    #[diesel(sql_type = diesel::sql_types::Numeric)]
+    pub reject_amount: Amount,
 }
 
 #[derive(Insertable)]
@@ -256,7 +643,10 @@ pub struct NewVotes<'a> {
     pub proposal_id: i64,
     pub is_agreed: bool,
     pub time: i64,
-    pub amount: i64,
+    pub amount: Amount,
+    pub updated_at_height: Option<i64>,
+    pub updated_by_transition_id: Option<&'a str>,
+    pub updated_at_timestamp: Option<i64>,
 }
 
 #[derive(Queryable, Selectable, Deserialize, Serialize)]
@@ -274,14 +664,21 @@ pub struct NewAutoIncrement {
     pub value: i64,
 }
 
-#[derive(Queryable, Selectable, Deserialize, Serialize)]
+#[derive(Queryable, Selectable, Clone, Deserialize, Serialize, SimpleObject, ToSchema)]
 #[diesel(table_name = balances)]
+#[graphql(name = "Balance", complex)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Balances {
     pub key: String,
     pub owner: String,
-    pub amount: i64,
+    pub amount: Amount,
     pub token_info_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_height: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_by_transition_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_timestamp: Option<i64>,
 }
 
 #[derive(Insertable)]
@@ -289,18 +686,28 @@ pub struct Balances {
 pub struct NewBalances<'a> {
     pub key: &'a str,
     pub owner: &'a str,
-    pub amount: i64,
+    pub amount: Amount,
     pub token_info_id: i64,
+    pub updated_at_height: Option<i64>,
+    pub updated_by_transition_id: Option<&'a str>,
+    pub updated_at_timestamp: Option<i64>,
 }
 
-#[derive(Queryable, Selectable, Deserialize, Serialize)]
+#[derive(Queryable, Selectable, Clone, Deserialize, Serialize, SimpleObject, ToSchema)]
 #[diesel(table_name = stake_amounts)]
+#[graphql(name = "Stake", complex)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct StakeAmounts {
     pub key: String,
     pub owner: String,
-    pub amount: i64,
+    pub amount: Amount,
     pub token_info_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_height: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_by_transition_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at_timestamp: Option<i64>,
 }
 
 #[derive(Insertable)]
@@ -308,8 +715,11 @@ pub struct StakeAmounts {
 pub struct NewStakeAmounts<'a> {
     pub key: &'a str,
     pub owner: &'a str,
-    pub amount: i64,
+    pub amount: Amount,
     pub token_info_id: i64,
+    pub updated_at_height: Option<i64>,
+    pub updated_by_transition_id: Option<&'a str>,
+    pub updated_at_timestamp: Option<i64>,
 }
 
 #[derive(Queryable, Selectable, Deserialize, Serialize)]
@@ -326,3 +736,59 @@ pub struct NewExtendPledgePeriod {
     pub key: i64,
     pub value: i64,
 }
+
+/// Rows derived by `program_handler`, which record the block and transition
+/// that last wrote them.
+pub trait Provenance {
+    fn clear_provenance(&mut self);
+}
+
+macro_rules! impl_provenance {
+    ($($model:ty),*) => {
+        $(
+            impl Provenance for $model {
+                fn clear_provenance(&mut self) {
+                    self.updated_at_height = None;
+                    self.updated_by_transition_id = None;
+                    self.updated_at_timestamp = None;
+                }
+            }
+        )*
+    };
+}
+
+impl_provenance!(
+    Daos,
+    Proposals,
+    Votes,
+    TokenInfos,
+    Balances,
+    StakeAmounts,
+    Profiles
+);
+
+impl<T: Provenance> Provenance for Vec<T> {
+    fn clear_provenance(&mut self) {
+        self.iter_mut().for_each(T::clear_provenance);
+    }
+}
+
+impl<T: Provenance> Provenance for Option<T> {
+    fn clear_provenance(&mut self) {
+        self.iter_mut().for_each(T::clear_provenance);
+    }
+}
+
+impl Provenance for RespProposalVotes {
+    fn clear_provenance(&mut self) {
+        self.votes.clear_provenance();
+    }
+}
+
+impl Provenance for RespVoterVote {
+    fn clear_provenance(&mut self) {
+        self.vote.clear_provenance();
+        self.proposal.clear_provenance();
+        self.dao.clear_provenance();
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{
+    amount::Amount,
+    events::{BalanceChanged, BlockIndexed, IndexerEvent, ProposalStatusChanged, VoteCast},
+    handlers, metrics,
+    models::{
+        Balances, Blocks, DaoTotal, Daos, Input, Output, Profiles, Proposals, ReqProfile,
+        ReqViewKey, RespBlock, RespError, RespProposalVotes, RespRecords, RespStats,
+        RespTokenHolding, RespTokenPledgers, RespTotal, RespViewKey, RespVoterVote, StakeAmounts,
+        Token, TokenInfoTotal, TokenInfos, Transactions, VoteTotals, Votes,
+    },
+    status::{self, RespProbe, SyncError, SyncStatus},
+    v1,
+};
+use axum::response::{Html, Json};
+use utoipa::{
+    openapi::{
+        self,
+        path::Operation,
+        security::{Http, HttpAuthScheme, SecurityScheme},
+        Deprecated,
+    },
+    Modify, OpenApi,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "NexusDAO Indexer API"),
+    paths(
+        v1::list_daos_handler,
+        v1::get_dao_handler,
+        v1::list_dao_proposals_handler,
+        v1::list_proposals_handler,
+        v1::get_proposal_handler,
+        v1::list_proposal_votes_handler,
+        v1::get_vote_handler,
+        v1::list_account_votes_handler,
+        v1::list_token_infos_handler,
+        v1::create_token_info_handler,
+        v1::get_token_info_handler,
+        v1::get_account_balances_handler,
+        v1::get_account_stakes_handler,
+        v1::get_account_profile_handler,
+        v1::put_account_profile_handler,
+        v1::register_view_key_handler,
+        v1::list_account_token_records_handler,
+        v1::list_account_token_holdings_handler,
+        v1::list_records_handler,
+        v1::get_latest_block_handler,
+        v1::get_block_handler,
+        v1::get_stats_handler,
+        v1::list_pledgers_handler,
+        handlers::records_handler,
+        handlers::events_handler,
+        handlers::get_profile_handler,
+        handlers::get_all_dao_ids_handler,
+        handlers::batch_get_dao_handler,
+        handlers::batch_get_token_id_of_dao_handler,
+        handlers::batch_get_proposal_id_of_dao_handler,
+        handlers::batch_get_token_info_handler,
+        handlers::get_balances_handler,
+        handlers::get_stakes_handler,
+        handlers::batch_get_pledgers_by_token_info_id,
+        handlers::get_pledgers_total_handler,
+        handlers::get_stake_funds_total_handler,
+        handlers::get_funds_total_handler,
+        handlers::get_creating_dao_proposal_ids_handler,
+        handlers::batch_get_proposals_handler,
+        handlers::get_all_proposal_ids_handler,
+        handlers::create_profile_handler,
+        handlers::update_profile_handler,
+        handlers::upsert_profile_handler,
+        handlers::create_token_info_handler,
+        status::healthz_handler,
+        status::readyz_handler,
+        status::status_handler,
+        metrics::metrics_handler,
+    ),
+    components(schemas(
+        Amount,
+        RespRecords,
+        Input,
+        Output,
+        RespBlock,
+        Blocks,
+        Transactions,
+        Profiles,
+        Daos,
+        TokenInfos,
+        Proposals,
+        Balances,
+        StakeAmounts,
+        Votes,
+        VoteTotals,
+        RespProposalVotes,
+        RespVoterVote,
+        RespTotal,
+        TokenInfoTotal,
+        DaoTotal,
+        RespStats,
+        RespTokenPledgers,
+        RespError,
+        ReqProfile,
+        Token,
+        ReqViewKey,
+        RespViewKey,
+        RespTokenHolding,
+        IndexerEvent,
+        BlockIndexed,
+        ProposalStatusChanged,
+        VoteCast,
+        BalanceChanged,
+        RespProbe,
+        SyncStatus,
+        SyncError,
+    )),
+    tags(
+        (name = "records", description = "Raw transitions of the indexed program"),
+        (name = "blocks", description = "Indexed blocks and their transactions"),
+        (name = "daos"),
+        (name = "proposals"),
+        (name = "tokens", description = "Token infos, balances and stakes"),
+        (name = "accounts", description = "Balances, stakes and profile of an address, and its token records once its view key is registered"),
+        (name = "votes", description = "Votes on proposals and voting history of accounts"),
+        (name = "profiles"),
+        (name = "stats", description = "Aggregated statistics"),
+        (name = "events", description = "Live updates"),
+        (name = "operations", description = "Probes, sync progress and metrics"),
+    ),
+    modifiers(&DeprecateLegacyPaths, &AccessToken)
+)]
+pub struct ApiDoc;
+
+/// The bearer token given by `POST /v1/view-keys`.
+struct AccessToken;
+
+impl Modify for AccessToken {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "access_token",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+    }
+}
+
+/// Unversioned paths that aren't part of the legacy API.
+const CURRENT_PATHS: [&str; 5] = ["/events", "/healthz", "/readyz", "/status", "/metrics"];
+
+/// Paths outside `/v1` are the unversioned API kept for existing clients.
+struct DeprecateLegacyPaths;
+
+impl Modify for DeprecateLegacyPaths {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            if path.starts_with("/v1/") || CURRENT_PATHS.contains(&path.as_str()) {
+                continue;
+            }
+            for operation in item.operations.values_mut() {
+                deprecate(operation);
+            }
+        }
+    }
+}
+
+fn deprecate(operation: &mut Operation) {
+    operation.deprecated = Some(Deprecated::True);
+}
+
+pub async fn openapi_handler() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI rendering `/openapi.json`. The UI assets are loaded from a CDN so
+/// nothing has to be bundled into the binary.
+pub async fn swagger_ui_handler() -> Html<&'static str> {
+    Html(
+        r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>NexusDAO Indexer API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>"##,
+    )
+}
@@ -0,0 +1,38 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+pub const DEFAULT_PAGE_LIMIT: i64 = 20;
+pub const MAX_PAGE_LIMIT: i64 = 100;
+
+/// `offset`/`limit` query parameters of paginated endpoints.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct Pagination {
+    /// Number of items to skip
+    pub offset: Option<i64>,
+    /// Maximum number of items to return, at most 100
+    pub limit: Option<i64>,
+}
+
+impl Pagination {
+    pub fn window(&self) -> (i64, i64) {
+        page(self.offset, self.limit)
+    }
+}
+
+/// Clamps user supplied `offset`/`limit` arguments to a sane window.
+pub fn page(offset: Option<i64>, limit: Option<i64>) -> (i64, i64) {
+    let offset = offset.unwrap_or(0).max(0);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(0, MAX_PAGE_LIMIT);
+    (offset, limit)
+}
+
+/// Applies `offset`/`limit` to a list that was already loaded in one go.
+pub fn paginate<T>(items: Vec<T>, offset: Option<i64>, limit: Option<i64>) -> Vec<T> {
+    let (offset, limit) = page(offset, limit);
+    items
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect()
+}
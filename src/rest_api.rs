@@ -0,0 +1,216 @@
+use crate::{
+    capture::{CaptureWriter, ReplayedMappings},
+    metrics::REST_API_RETRIES,
+};
+use anyhow::{anyhow, Error};
+use clap::Args;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use tokio_retry::strategy::{jitter, ExponentialBackoff};
+use tracing::warn;
+use ureq::{Agent, AgentBuilder};
+
+/// How requests to the Aleo REST API are timed out and retried, common to
+/// all commands.
+#[derive(Args, Clone)]
+pub struct RestApiArgs {
+    /// Seconds to wait for a response of the Aleo REST API, including the connection
+    #[arg(long, global = true, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    pub rest_api_timeout: u64,
+
+    /// Seconds to wait for a connection to the Aleo REST API
+    #[arg(long, global = true, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+    pub rest_api_connect_timeout: u64,
+
+    /// Retries of a request after a connection error, a timeout or a 429 or 5xx status, up to 100
+    #[arg(long, global = true, default_value_t = 3, value_parser = clap::value_parser!(u32).range(..=100))]
+    pub rest_api_retries: u32,
+
+    /// Milliseconds before the first retry, doubled for every other one and jittered
+    #[arg(long, global = true, default_value_t = 250, value_parser = clap::value_parser!(u64).range(1..))]
+    pub rest_api_retry_delay: u64,
+
+    /// Longest delay between two retries, in milliseconds
+    #[arg(long, global = true, default_value_t = 10_000, value_parser = clap::value_parser!(u64).range(1..))]
+    pub rest_api_max_retry_delay: u64,
+}
+
+/// Client of one or more Aleo REST API endpoints serving the same chain.
+/// Requests go to the endpoint that answered last, and retries move on to
+/// the next one.
+#[derive(Clone)]
+pub struct RestApi {
+    endpoints: Vec<String>,
+    current: Arc<AtomicUsize>,
+    agent: Agent,
+    retries: usize,
+    retry_delay: u64,
+    max_retry_delay: Duration,
+    /// Records the mapping values fetched, see `capture`.
+    capture: Option<Arc<CaptureWriter>>,
+    /// Answers the mapping value requests instead of the endpoints.
+    replay: Option<ReplayedMappings>,
+}
+
+impl RestApi {
+    pub fn new(endpoints: Vec<String>, args: &RestApiArgs) -> Result<Self, Error> {
+        let endpoints: Vec<String> = endpoints
+            .iter()
+            .map(|endpoint| endpoint.trim().trim_end_matches('/').to_string())
+            .filter(|endpoint| !endpoint.is_empty())
+            .collect();
+        if endpoints.is_empty() {
+            return Err(anyhow!("No Aleo REST API endpoint"));
+        }
+
+        Ok(RestApi {
+            endpoints,
+            current: Arc::new(AtomicUsize::new(0)),
+            agent: AgentBuilder::new()
+                .timeout(Duration::from_secs(args.rest_api_timeout))
+                .timeout_connect(Duration::from_secs(args.rest_api_connect_timeout))
+                .build(),
+            retries: args.rest_api_retries as usize,
+            retry_delay: args.rest_api_retry_delay,
+            max_retry_delay: Duration::from_millis(args.rest_api_max_retry_delay),
+            capture: None,
+            replay: None,
+        })
+    }
+
+    /// Records the mapping values fetched to `capture`.
+    pub fn capturing(mut self, capture: Arc<CaptureWriter>) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Answers the mapping value requests from `replay` instead of the endpoints.
+    pub fn replaying(mut self, replay: ReplayedMappings) -> Self {
+        self.replay = Some(replay);
+        self
+    }
+
+    /// Tries each endpoint once without waiting between them, for requests
+    /// made in a database transaction: a record whose mapping values can't
+    /// be fetched fails right away and is retried later from
+    /// `failed_records`, instead of keeping the transaction open.
+    pub fn without_backoff(mut self) -> Self {
+        self.retries = self.endpoints.len() - 1;
+        self.retry_delay = 0;
+        self.max_retry_delay = Duration::ZERO;
+        self
+    }
+
+    /// Value of a mapping entry, `None` when the entry doesn't exist.
+    pub fn mapping_value(
+        &self,
+        program_id: &str,
+        mapping_name: &str,
+        mapping_key: &str,
+    ) -> Result<Option<String>, Error> {
+        if let Some(replay) = &self.replay {
+            return replay.next(program_id, mapping_name, mapping_key);
+        }
+
+        let value = self
+            .get(&format!(
+                "/testnet3/program/{program_id}/mapping/{mapping_name}/{mapping_key}"
+            ))
+            .map(|value| (value.trim() != "null").then_some(value));
+
+        if let Some(capture) = &self.capture {
+            capture.mapping(program_id, mapping_name, mapping_key, &value)?;
+        }
+        value
+    }
+
+    pub fn latest_height(&self) -> Result<i64, Error> {
+        Ok(self.get("/testnet3/latest/height")?.trim().parse()?)
+    }
+
+    /// Body of a GET of `path`, retrying transient errors with a backoff.
+    /// Blocks the current thread.
+    pub fn get(&self, path: &str) -> Result<String, Error> {
+        let mut delays = ExponentialBackoff::from_millis(2)
+            .factor(self.retry_delay / 2)
+            .max_delay(self.max_retry_delay)
+            .map(jitter)
+            .take(self.retries);
+
+        let mut index = self.current.load(Ordering::Relaxed);
+        loop {
+            let url = format!("{}{}", self.endpoints[index % self.endpoints.len()], path);
+            let err = match self.agent.get(&url).call() {
+                Ok(response) => {
+                    self.current
+                        .store(index % self.endpoints.len(), Ordering::Relaxed);
+                    return Ok(response.into_string()?);
+                }
+                Err(err) if is_transient(&err) => err,
+                Err(err) => return Err(err.into()),
+            };
+
+            match delays.next() {
+                Some(delay) => {
+                    warn!("Retrying in {:?} after {}", delay, err);
+                    REST_API_RETRIES.inc();
+                    index += 1;
+                    thread::sleep(delay);
+                }
+                None => return Err(err.into()),
+            }
+        }
+    }
+}
+
+/// Whether a request may succeed when sent again.
+fn is_transient(err: &ureq::Error) -> bool {
+    match err {
+        ureq::Error::Transport(_) => true,
+        ureq::Error::Status(status, _) => *status == 408 || *status == 429 || *status >= 500,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rest_api_mock::MockRestApi;
+    use std::{net::TcpListener, time::Instant};
+
+    #[test]
+    fn without_backoff_fails_over_without_waiting() {
+        let mock = MockRestApi::start();
+        mock.set_mapping("p.aleo", "daos", "1u64", "{ id: 1u64 }");
+        // A port nothing listens on any more refuses the connection.
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let rest_api = RestApi::new(
+            vec![format!("http://{}", closed), mock.url().to_string()],
+            &RestApiArgs {
+                rest_api_timeout: 5,
+                rest_api_connect_timeout: 5,
+                rest_api_retries: 3,
+                rest_api_retry_delay: 60_000,
+                rest_api_max_retry_delay: 60_000,
+            },
+        )
+        .unwrap()
+        .without_backoff();
+
+        let start = Instant::now();
+        assert_eq!(
+            rest_api.mapping_value("p.aleo", "daos", "1u64").unwrap(),
+            Some("\"{ id: 1u64 }\"".to_string())
+        );
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
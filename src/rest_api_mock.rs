@@ -0,0 +1,115 @@
+use crate::rest_api::{RestApi, RestApiArgs};
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// Aleo REST API serving canned mapping values, to exercise the handlers
+/// without a node. Mapping entries that weren't set answer `null`, like
+/// missing ones. It listens on a local port until dropped.
+pub struct MockRestApi {
+    url: String,
+    values: Arc<Mutex<HashMap<String, String>>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MockRestApi {
+    pub fn start() -> MockRestApi {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let values = Arc::new(Mutex::new(HashMap::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let (thread_values, thread_stopped) = (values.clone(), stopped.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if thread_stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    respond(stream, &thread_values);
+                }
+            }
+        });
+
+        MockRestApi {
+            url,
+            values,
+            stopped,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// A client of this server that doesn't retry.
+    pub fn rest_api(&self) -> RestApi {
+        RestApi::new(
+            vec![self.url.clone()],
+            &RestApiArgs {
+                rest_api_timeout: 5,
+                rest_api_connect_timeout: 5,
+                rest_api_retries: 0,
+                rest_api_retry_delay: 0,
+                rest_api_max_retry_delay: 0,
+            },
+        )
+        .unwrap()
+    }
+
+    /// Sets a mapping entry, `value` being the plaintext as Aleo prints it,
+    /// e.g. `{ id: 1u64, name: 2field }`.
+    pub fn set_mapping(&self, program_id: &str, mapping_name: &str, key: &str, value: &str) {
+        self.values.lock().unwrap().insert(
+            format!("/testnet3/program/{program_id}/mapping/{mapping_name}/{key}"),
+            // The API returns the plaintext as a JSON string.
+            serde_json::to_string(value).unwrap(),
+        );
+    }
+}
+
+impl Drop for MockRestApi {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // Wakes the listener up so that it sees the flag.
+        let _ = TcpStream::connect(self.url.trim_start_matches("http://"));
+    }
+}
+
+/// Answers a single GET and closes the connection.
+fn respond(mut stream: TcpStream, values: &Mutex<HashMap<String, String>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // Skips the headers, the requests have no body.
+    let mut line = String::new();
+    while reader.read_line(&mut line).map_or(false, |read| read > 2) {
+        line.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let body = values
+        .lock()
+        .unwrap()
+        .get(path)
+        .cloned()
+        .unwrap_or_else(|| "null".to_string());
+
+    let _ = write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let _ = stream.shutdown(Shutdown::Both);
+}
@@ -7,9 +7,107 @@ use crate::handlers::{
     get_pledgers_total_handler, get_profile_handler, get_stake_funds_total_handler,
     get_stakes_handler, records_handler, update_profile_handler, upsert_profile_handler,
 };
-use axum::{routing::get, Router};
+use crate::v1;
+use crate::{
+    graphql::{build_schema, graphql_handler, graphql_playground_handler},
+    handlers::events_handler,
+    metrics::{metrics_handler, track_http},
+    openapi::{openapi_handler, swagger_ui_handler},
+    status::{healthz_handler, readyz_handler, status_handler},
+    telemetry::http_span,
+};
+use async_graphql_axum::GraphQLSubscription;
+use axum::{
+    http::{header::HeaderName, HeaderValue},
+    middleware,
+    routing::{get, post},
+    Extension, Router,
+};
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    set_header::SetResponseHeaderLayer,
+    trace::{DefaultOnResponse, TraceLayer},
+};
+use tracing::Level;
 
 pub fn routes() -> Router {
+    let schema = build_schema();
+
+    Router::new()
+        .route(
+            "/graphql",
+            get(graphql_playground_handler).post(graphql_handler),
+        )
+        .route_service("/graphql/ws", GraphQLSubscription::new(schema.clone()))
+        .route("/events", get(events_handler))
+        .route("/openapi.json", get(openapi_handler))
+        .route("/swagger-ui", get(swagger_ui_handler))
+        .route("/metrics", get(metrics_handler))
+        .route("/healthz", get(healthz_handler))
+        .route("/readyz", get(readyz_handler))
+        .route("/status", get(status_handler))
+        .nest("/v1", v1_routes())
+        .merge(legacy_routes())
+        .route_layer(middleware::from_fn(track_http))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(http_span)
+                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        )
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+        .layer(Extension(schema))
+}
+
+pub fn v1_routes() -> Router {
+    Router::new()
+        .route("/daos", get(v1::list_daos_handler))
+        .route("/daos/:id", get(v1::get_dao_handler))
+        .route("/daos/:id/proposals", get(v1::list_dao_proposals_handler))
+        .route("/proposals", get(v1::list_proposals_handler))
+        .route("/proposals/:id", get(v1::get_proposal_handler))
+        .route("/proposals/:id/votes", get(v1::list_proposal_votes_handler))
+        .route("/votes/:key", get(v1::get_vote_handler))
+        .route(
+            "/token-infos",
+            get(v1::list_token_infos_handler).post(v1::create_token_info_handler),
+        )
+        .route("/token-infos/:id", get(v1::get_token_info_handler))
+        .route(
+            "/accounts/:address/balances",
+            get(v1::get_account_balances_handler),
+        )
+        .route(
+            "/accounts/:address/stakes",
+            get(v1::get_account_stakes_handler),
+        )
+        .route(
+            "/accounts/:address/profile",
+            get(v1::get_account_profile_handler).put(v1::put_account_profile_handler),
+        )
+        .route(
+            "/accounts/:address/votes",
+            get(v1::list_account_votes_handler),
+        )
+        .route(
+            "/accounts/:address/token-records",
+            get(v1::list_account_token_records_handler),
+        )
+        .route(
+            "/accounts/:address/token-holdings",
+            get(v1::list_account_token_holdings_handler),
+        )
+        .route("/view-keys", post(v1::register_view_key_handler))
+        .route("/records", get(v1::list_records_handler))
+        .route("/blocks/latest", get(v1::get_latest_block_handler))
+        .route("/blocks/:height", get(v1::get_block_handler))
+        .route("/stats", get(v1::get_stats_handler))
+        .route("/stats/pledgers", get(v1::list_pledgers_handler))
+}
+
+/// Unversioned routes kept for existing clients. Every response carries a
+/// `Deprecation` header pointing them to `/v1`.
+pub fn legacy_routes() -> Router {
     Router::new()
         .route("/records", get(records_handler))
         .route("/profile/:address", get(get_profile_handler))
@@ -37,4 +135,12 @@ pub fn routes() -> Router {
         .route("/update_profile", get(update_profile_handler))
         .route("/upsert_profile", get(upsert_profile_handler))
         .route("/create_token_info", get(create_token_info_handler))
+        .layer(SetResponseHeaderLayer::overriding(
+            HeaderName::from_static("deprecation"),
+            HeaderValue::from_static("true"),
+        ))
+        .layer(SetResponseHeaderLayer::overriding(
+            HeaderName::from_static("link"),
+            HeaderValue::from_static("</v1>; rel=\"successor-version\""),
+        ))
 }
@@ -11,8 +11,47 @@ diesel::table! {
     balances (key) {
         key -> Text,
         owner -> Text,
-        amount -> Int8,
+        amount -> Numeric,
         token_info_id -> Int8,
+        updated_at_height -> Nullable<Int8>,
+        updated_by_transition_id -> Nullable<Text>,
+        updated_at_timestamp -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    blocks (height) {
+        height -> Int8,
+        block_hash -> Text,
+        previous_hash -> Nullable<Text>,
+        timestamp -> Int8,
+        transaction_count -> Int8,
+        transition_count -> Int8,
+    }
+}
+
+diesel::table! {
+    cursors (name) {
+        name -> Text,
+        cursor -> Text,
+        height -> Int8,
+        block_hash -> Text,
+    }
+}
+
+diesel::table! {
+    failed_records (transition_id) {
+        transition_id -> Text,
+        program -> Text,
+        function -> Text,
+        height -> Int8,
+        record -> Bytea,
+        error -> Text,
+        attempts -> Int8,
+        first_failed_at -> Int8,
+        last_failed_at -> Int8,
+        next_retry_at -> Int8,
+        gave_up -> Bool,
     }
 }
 
@@ -26,11 +65,14 @@ diesel::table! {
         icon -> Text,
         description -> Text,
         official_link -> Text,
-        proposal_count -> Int8,
-        pass_proposal_count -> Int8,
-        vote_count -> Int8,
-        passed_votes_proportion -> Int8,
-        passed_tokens_proportion -> Int8,
+        proposal_count -> Numeric,
+        pass_proposal_count -> Numeric,
+        vote_count -> Numeric,
+        passed_votes_proportion -> Numeric,
+        passed_tokens_proportion -> Numeric,
+        updated_at_height -> Nullable<Int8>,
+        updated_by_transition_id -> Nullable<Text>,
+        updated_at_timestamp -> Nullable<Int8>,
     }
 }
 
@@ -58,6 +100,9 @@ diesel::table! {
         name -> Text,
         avatar -> Text,
         bio -> Text,
+        updated_at_height -> Nullable<Int8>,
+        updated_by_transition_id -> Nullable<Text>,
+        updated_at_timestamp -> Nullable<Int8>,
     }
 }
 
@@ -73,9 +118,12 @@ diesel::table! {
         duration -> Int8,
         #[sql_name = "type"]
         type_ -> Int8,
-        adopt -> Int8,
-        reject -> Int8,
+        adopt -> Numeric,
+        reject -> Numeric,
         status -> Int8,
+        updated_at_height -> Nullable<Int8>,
+        updated_by_transition_id -> Nullable<Text>,
+        updated_at_timestamp -> Nullable<Int8>,
     }
 }
 
@@ -84,8 +132,8 @@ diesel::table! {
         transition_id -> Text,
         program -> Text,
         function -> Text,
-        inputs -> Text,
-        outputs -> Text,
+        inputs -> Jsonb,
+        outputs -> Jsonb,
         block_hash -> Text,
         previous_hash -> Text,
         transaction_id -> Text,
@@ -95,23 +143,60 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    record_inputs (transition_id, position) {
+        transition_id -> Text,
+        position -> Int8,
+        #[sql_name = "type"]
+        type_ -> Text,
+        id -> Text,
+        value -> Text,
+        tag -> Text,
+    }
+}
+
+diesel::table! {
+    record_outputs (transition_id, position) {
+        transition_id -> Text,
+        position -> Int8,
+        #[sql_name = "type"]
+        type_ -> Text,
+        id -> Text,
+        checksum -> Text,
+        value -> Text,
+    }
+}
+
 diesel::table! {
     stake_amounts (key) {
         key -> Text,
         owner -> Text,
-        amount -> Int8,
+        amount -> Numeric,
         token_info_id -> Int8,
+        updated_at_height -> Nullable<Int8>,
+        updated_by_transition_id -> Nullable<Text>,
+        updated_at_timestamp -> Nullable<Int8>,
     }
 }
 
 diesel::table! {
-    token (owner) {
+    token (commitment) {
+        commitment -> Text,
         owner -> Text,
-        gates -> Int8,
+        gates -> Numeric,
         token_info_id -> Int8,
-        amount -> Int8,
+        amount -> Numeric,
         expires -> Int8,
         staked_at -> Int8,
+        function -> Text,
+        created_at_height -> Int8,
+        created_by_transition_id -> Text,
+        created_at_timestamp -> Int8,
+        tag -> Nullable<Text>,
+        serial_number -> Nullable<Text>,
+        spent_at_height -> Nullable<Int8>,
+        spent_by_transition_id -> Nullable<Text>,
+        private -> Bool,
     }
 }
 
@@ -120,12 +205,33 @@ diesel::table! {
         id -> Int8,
         name -> Text,
         symbol -> Text,
-        supply -> Int8,
+        supply -> Numeric,
         decimals -> Int8,
-        max_mint_amount -> Int8,
-        minted_amount -> Int8,
+        max_mint_amount -> Numeric,
+        minted_amount -> Numeric,
         dao_id -> Int8,
         only_creator_can_mint -> Bool,
+        updated_at_height -> Nullable<Int8>,
+        updated_by_transition_id -> Nullable<Text>,
+        updated_at_timestamp -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    transactions (transaction_id) {
+        transaction_id -> Text,
+        height -> Int8,
+        transition_count -> Int8,
+    }
+}
+
+diesel::table! {
+    view_keys (address) {
+        address -> Text,
+        encrypted_view_key -> Bytea,
+        access_token_hash -> Text,
+        registered_at -> Int8,
+        backfilled_at_height -> Nullable<Int8>,
     }
 }
 
@@ -136,21 +242,39 @@ diesel::table! {
         proposal_id -> Int8,
         is_agreed -> Bool,
         time -> Int8,
-        amount -> Int8,
+        amount -> Numeric,
+        updated_at_height -> Nullable<Int8>,
+        updated_by_transition_id -> Nullable<Text>,
+        updated_at_timestamp -> Nullable<Int8>,
     }
 }
 
+diesel::joinable!(balances -> token_infos (token_info_id));
+diesel::joinable!(proposals -> daos (dao_id));
+diesel::joinable!(record_inputs -> record (transition_id));
+diesel::joinable!(record_outputs -> record (transition_id));
+diesel::joinable!(stake_amounts -> token_infos (token_info_id));
+diesel::joinable!(transactions -> blocks (height));
+diesel::joinable!(votes -> proposals (proposal_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
     auto_increment,
     balances,
+    blocks,
+    cursors,
     daos,
     daos_schema,
     extend_pledge_period,
+    failed_records,
     profiles,
     proposals,
     record,
+    record_inputs,
+    record_outputs,
     stake_amounts,
     token,
     token_infos,
+    transactions,
+    view_keys,
     votes,
 );
@@ -0,0 +1,555 @@
+use crate::{
+    events::{BlockIndexed, IndexerEvent},
+    metrics::DB_WRITE_DURATION,
+    models::{Cursors, Input, Output},
+    program_handler::{backfill_view_keys, program_handler},
+    proto::Records,
+    rest_api::RestApi,
+    storage::run_with_storage,
+};
+use anyhow::{anyhow, Context, Error};
+use clap::ValueEnum;
+use kafka::{
+    client::{FetchOffset, FetchPartition, KafkaClient},
+    producer::{Producer, Record, RequiredAcks},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Seek, SeekFrom, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use tracing::{error, info, warn};
+
+/// Name of the database sink's cursor in the `cursors` table.
+pub const DATABASE_SINK_NAME: &str = "database";
+
+/// Kafka only returns whole messages, so this bounds the size of a block.
+const KAFKA_MAX_MESSAGE_BYTES: i32 = 16 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SinkKind {
+    /// The indexer database, see `DATABASE_URL`
+    Database,
+    /// Newline-delimited JSON file, one line per block
+    Ndjson,
+    /// Kafka or Redpanda topic, one message per block
+    Kafka,
+}
+
+/// A block of the Substreams module output.
+pub struct SinkBlock {
+    pub height: i64,
+    pub block_hash: String,
+    pub timestamp: i64,
+    pub cursor: String,
+    pub records: Records,
+}
+
+impl SinkBlock {
+    /// How the NDJSON and Kafka sinks serialize a block.
+    fn to_json(&self) -> serde_json::Value {
+        let records = self
+            .records
+            .records
+            .iter()
+            .map(|record| {
+                let inputs = record
+                    .inputs
+                    .iter()
+                    .map(|input| Input {
+                        r#type: input.r#type.clone(),
+                        id: input.id.clone(),
+                        value: input.value.clone(),
+                        tag: input.tag.clone(),
+                    })
+                    .collect::<Vec<Input>>();
+                let outputs = record
+                    .outputs
+                    .iter()
+                    .map(|output| Output {
+                        r#type: output.r#type.clone(),
+                        id: output.id.clone(),
+                        checksum: output.checksum.clone(),
+                        value: output.value.clone(),
+                    })
+                    .collect::<Vec<Output>>();
+
+                json!({
+                    "program": record.program,
+                    "function": record.function,
+                    "inputs": inputs,
+                    "outputs": outputs,
+                    "finalize": record.finalize,
+                    "block_hash": record.block_hash,
+                    "previous_hash": record.previous_hash,
+                    "transaction_id": record.transaction_id,
+                    "transition_id": record.transition_id,
+                    "network": record.network,
+                    "height": record.height,
+                    "timestamp": record.timestamp,
+                })
+            })
+            .collect::<Vec<serde_json::Value>>();
+
+        json!({
+            "height": self.height,
+            "block_hash": self.block_hash,
+            "timestamp": self.timestamp,
+            "cursor": self.cursor,
+            "records": records,
+        })
+    }
+
+    /// How the NDJSON and Kafka sinks serialize the undoing of a block.
+    fn to_undo_json(&self) -> serde_json::Value {
+        json!({
+            "undo": true,
+            "height": self.height,
+            "block_hash": self.block_hash,
+            "cursor": self.cursor,
+        })
+    }
+
+    fn cursor(&self, name: &str) -> Cursors {
+        Cursors {
+            name: name.to_string(),
+            cursor: self.cursor.clone(),
+            height: self.height,
+            block_hash: self.block_hash.clone(),
+        }
+    }
+
+    /// The cursor after this block was undone, at the block before it, whose
+    /// hash is `block_hash` when the sink knows it.
+    fn undo_cursor(&self, name: &str, block_hash: Option<String>) -> Cursors {
+        Cursors {
+            name: name.to_string(),
+            cursor: self.cursor.clone(),
+            height: self.height - 1,
+            block_hash: block_hash.unwrap_or_default(),
+        }
+    }
+}
+
+/// Where synced blocks are written. A sink stores the cursor of the last
+/// block it wrote together with the block, so that after a restart it neither
+/// misses nor repeats a block.
+pub trait Sink: Send {
+    fn name(&self) -> &str;
+
+    /// The last block written, `None` when nothing was written yet.
+    fn load_cursor(&mut self) -> Result<Option<Cursors>, Error>;
+
+    /// Writes `block` and moves the cursor to it, or neither.
+    fn write_block(&mut self, block: &SinkBlock) -> Result<(), Error>;
+
+    /// Removes `block`, undone by a fork, and moves the cursor to the block
+    /// before it, or neither. Blocks after it were undone before.
+    fn undo_block(&mut self, block: &SinkBlock) -> Result<(), Error>;
+}
+
+/// Indexes blocks into the database: records, blocks and the rows derived by
+/// `program_handler`, after the records of newly registered view keys, then
+/// publishes `BlockIndexed`.
+pub struct DatabaseSink {
+    rest_api: RestApi,
+    program_id: String,
+}
+
+impl DatabaseSink {
+    pub fn new(rest_api: RestApi, program_id: String) -> Self {
+        DatabaseSink {
+            rest_api,
+            program_id,
+        }
+    }
+}
+
+impl Sink for DatabaseSink {
+    fn name(&self) -> &str {
+        DATABASE_SINK_NAME
+    }
+
+    fn load_cursor(&mut self) -> Result<Option<Cursors>, Error> {
+        run_with_storage(|conn| conn.get_cursor(self.name()))
+    }
+
+    fn write_block(&mut self, block: &SinkBlock) -> Result<(), Error> {
+        let cursor = block.cursor(self.name());
+        let mut commit_start = None;
+
+        run_with_storage(|conn| {
+            conn.transaction(&mut |conn| {
+                let timer = DB_WRITE_DURATION
+                    .with_label_values(&["insert_records"])
+                    .start_timer();
+                conn.insert_records(&block.records)
+                    .context("insertion in db failed")?;
+                timer.observe_duration();
+
+                let timer = DB_WRITE_DURATION
+                    .with_label_values(&["insert_block"])
+                    .start_timer();
+                conn.insert_block(
+                    block.height,
+                    &block.block_hash,
+                    block.timestamp,
+                    &block.records,
+                )
+                .context("block insertion in db failed")?;
+                timer.observe_duration();
+
+                for chain_break in conn.get_chain_breaks(block.height, block.height + 1)? {
+                    error!(
+                        "Block {} does not link to block {}: previous hash {}, expected {}",
+                        chain_break.height,
+                        chain_break.height - 1,
+                        chain_break.previous_hash,
+                        chain_break.parent_hash
+                    );
+                }
+
+                backfill_view_keys(conn, &self.program_id, block.height)?;
+                program_handler(conn, &self.rest_api, &block.records, &self.program_id)?;
+
+                conn.publish_event(IndexerEvent::Block(BlockIndexed {
+                    height: block.height,
+                    block_hash: block.block_hash.clone(),
+                    timestamp: block.timestamp,
+                    record_count: block.records.records.len() as i64,
+                }));
+                conn.set_cursor(&cursor)?;
+
+                commit_start = Some(Instant::now());
+                Ok(())
+            })
+        })?;
+
+        if let Some(start) = commit_start {
+            DB_WRITE_DURATION
+                .with_label_values(&["commit"])
+                .observe(start.elapsed().as_secs_f64());
+        }
+
+        Ok(())
+    }
+
+    fn undo_block(&mut self, block: &SinkBlock) -> Result<(), Error> {
+        run_with_storage(|conn| {
+            conn.transaction(&mut |conn| {
+                conn.delete_blocks_from(block.height)?;
+                let block_hash = conn.get_block_hash(block.height - 1)?;
+                conn.set_cursor(&block.undo_cursor(self.name(), block_hash))
+            })
+        })
+    }
+}
+
+/// Position of an NDJSON file after its last complete block.
+#[derive(Deserialize, Serialize)]
+struct NdjsonCursor {
+    #[serde(flatten)]
+    cursor: Cursors,
+    offset: u64,
+}
+
+/// Appends one JSON line per block to a file. The cursor is kept next to it
+/// in `<path>.cursor` with the length of the file at that block, and anything
+/// written after it is dropped when the sink is opened again.
+pub struct NdjsonSink {
+    path: PathBuf,
+    cursor_path: PathBuf,
+    file: File,
+    offset: u64,
+}
+
+impl NdjsonSink {
+    pub fn open(path: PathBuf) -> Result<Self, Error> {
+        let mut cursor_path = path.clone().into_os_string();
+        cursor_path.push(".cursor");
+        let cursor_path = PathBuf::from(cursor_path);
+
+        let offset = match fs::read(&cursor_path) {
+            Ok(content) => serde_json::from_slice::<NdjsonCursor>(&content)?.offset,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("open {}", path.display()))?;
+        let length = file.metadata()?.len();
+        if offset == 0 && length > 0 {
+            return Err(anyhow!(
+                "{} has no cursor at {}, move the file away to start over",
+                path.display(),
+                cursor_path.display()
+            ));
+        }
+        if length > offset {
+            warn!(
+                "Dropping {} bytes written after the cursor of {}",
+                length - offset,
+                path.display()
+            );
+        }
+        file.set_len(offset)?;
+        file.seek(SeekFrom::Start(offset))?;
+
+        Ok(NdjsonSink {
+            path,
+            cursor_path,
+            file,
+            offset,
+        })
+    }
+
+    /// Appends `value` as a line, then moves the cursor after it.
+    fn append(&mut self, value: &serde_json::Value, cursor: Cursors) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(value)?;
+        line.push(b'\n');
+        self.file
+            .write_all(&line)
+            .and_then(|_| self.file.sync_data())
+            .with_context(|| format!("write {}", self.path.display()))?;
+
+        let cursor = NdjsonCursor {
+            cursor,
+            offset: self.offset + line.len() as u64,
+        };
+        // Renaming is atomic, so the cursor is either the old or the new one.
+        let mut tmp_path = self.cursor_path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, serde_json::to_vec(&cursor)?)?;
+        File::open(&tmp_path)?.sync_all()?;
+        fs::rename(&tmp_path, &self.cursor_path)?;
+        self.offset = cursor.offset;
+
+        Ok(())
+    }
+}
+
+impl Sink for NdjsonSink {
+    fn name(&self) -> &str {
+        "ndjson"
+    }
+
+    fn load_cursor(&mut self) -> Result<Option<Cursors>, Error> {
+        if self.offset == 0 {
+            return Ok(None);
+        }
+        let content = fs::read(&self.cursor_path)?;
+        Ok(Some(
+            serde_json::from_slice::<NdjsonCursor>(&content)?.cursor,
+        ))
+    }
+
+    fn write_block(&mut self, block: &SinkBlock) -> Result<(), Error> {
+        self.append(&block.to_json(), block.cursor(self.name()))
+    }
+
+    /// Undone blocks are left in the file, followed by a line telling so.
+    fn undo_block(&mut self, block: &SinkBlock) -> Result<(), Error> {
+        self.append(&block.to_undo_json(), block.undo_cursor(self.name(), None))
+    }
+}
+
+/// Produces one message per block, keyed by height, to partition 0 of a topic
+/// so that blocks stay in order. The last message of the partition is the
+/// cursor.
+pub struct KafkaSink {
+    brokers: Vec<String>,
+    topic: String,
+    producer: Producer,
+}
+
+impl KafkaSink {
+    pub fn open(brokers: Vec<String>, topic: String) -> Result<Self, Error> {
+        let producer = Producer::from_hosts(brokers.clone())
+            .with_ack_timeout(Duration::from_secs(10))
+            .with_required_acks(RequiredAcks::All)
+            .create()
+            .with_context(|| format!("connect to Kafka brokers {}", brokers.join(",")))?;
+
+        Ok(KafkaSink {
+            brokers,
+            topic,
+            producer,
+        })
+    }
+
+    fn send(&mut self, height: i64, value: &serde_json::Value) -> Result<(), Error> {
+        let value = serde_json::to_string(value)?;
+        self.producer.send(
+            &Record::from_key_value(&self.topic, height.to_string(), value).with_partition(0),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// The fields of a block or undo message that make up the cursor of the sink.
+#[derive(Deserialize)]
+struct KafkaCursor {
+    cursor: String,
+    height: i64,
+    block_hash: String,
+    #[serde(default)]
+    undo: bool,
+}
+
+impl Sink for KafkaSink {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    fn load_cursor(&mut self) -> Result<Option<Cursors>, Error> {
+        let mut client = KafkaClient::new(self.brokers.clone());
+        client.set_fetch_max_bytes_per_partition(KAFKA_MAX_MESSAGE_BYTES);
+        client.load_metadata(&[&self.topic])?;
+
+        let latest = client
+            .fetch_topic_offsets(&self.topic, FetchOffset::Latest)?
+            .into_iter()
+            .find(|offset| offset.partition == 0)
+            .ok_or_else(|| anyhow!("topic {} has no partition 0", self.topic))?
+            .offset;
+        if latest == 0 {
+            return Ok(None);
+        }
+
+        let responses = client.fetch_messages_for_partition(&FetchPartition::new(
+            &self.topic,
+            0,
+            latest - 1,
+        ))?;
+        for response in responses.iter() {
+            for topic in response.topics() {
+                for partition in topic.partitions() {
+                    let data = partition.data().map_err(|err| anyhow!("{}", err))?;
+                    if let Some(message) = data.messages().last() {
+                        let block: KafkaCursor = serde_json::from_slice(message.value)
+                            .with_context(|| {
+                                format!(
+                                    "the last message of topic {} has no valid cursor",
+                                    self.topic
+                                )
+                            })?;
+                        // After an undo message the sink is at the block
+                        // before, whose hash it doesn't know.
+                        let (height, block_hash) = if block.undo {
+                            (block.height - 1, String::new())
+                        } else {
+                            (block.height, block.block_hash)
+                        };
+                        return Ok(Some(Cursors {
+                            name: self.name().to_string(),
+                            cursor: block.cursor,
+                            height,
+                            block_hash,
+                        }));
+                    }
+                }
+            }
+        }
+
+        Err(anyhow!(
+            "the last message of topic {} could not be read",
+            self.topic
+        ))
+    }
+
+    fn write_block(&mut self, block: &SinkBlock) -> Result<(), Error> {
+        self.send(block.height, &block.to_json())
+    }
+
+    /// Undone blocks stay in the topic, followed by a message telling so.
+    fn undo_block(&mut self, block: &SinkBlock) -> Result<(), Error> {
+        self.send(block.height, &block.to_undo_json())
+    }
+}
+
+/// The sinks `sync` writes to, each with the height of its cursor.
+pub struct Sinks {
+    sinks: Vec<(Box<dyn Sink>, Option<i64>)>,
+    /// Cursor to resume streaming from, the earliest of the sinks.
+    start_cursor: Option<String>,
+}
+
+impl Sinks {
+    pub fn open(sinks: Vec<Box<dyn Sink>>) -> Result<Self, Error> {
+        let mut opened = Vec::new();
+        let mut cursors = Vec::new();
+
+        for mut sink in sinks {
+            let cursor = sink.load_cursor()?;
+            match &cursor {
+                Some(cursor) => info!(
+                    "Sink {} is at block {} (cursor {})",
+                    sink.name(),
+                    cursor.height,
+                    cursor.cursor
+                ),
+                None => info!("Sink {} starts from the start block", sink.name()),
+            }
+            opened.push((sink, cursor.as_ref().map(|cursor| cursor.height)));
+            cursors.push(cursor);
+        }
+
+        // A sink without a cursor needs the stream from the start block.
+        let start_cursor = if cursors.iter().any(Option::is_none) {
+            None
+        } else {
+            cursors
+                .into_iter()
+                .flatten()
+                .min_by_key(|cursor| cursor.height)
+                .map(|cursor| cursor.cursor)
+        };
+
+        Ok(Sinks {
+            sinks: opened,
+            start_cursor,
+        })
+    }
+
+    pub fn start_cursor(&self) -> Option<String> {
+        self.start_cursor.clone()
+    }
+
+    /// Removes `block`, undone by a fork, from the sinks that wrote it, so
+    /// that they write the block replacing it.
+    pub fn undo_block(&mut self, block: &SinkBlock) -> Result<(), Error> {
+        for (sink, height) in self.sinks.iter_mut() {
+            if height.map_or(true, |height| block.height > height) {
+                continue;
+            }
+            sink.undo_block(block).with_context(|| {
+                format!("sink {} failed to undo block {}", sink.name(), block.height)
+            })?;
+            *height = Some(block.height - 1);
+        }
+
+        Ok(())
+    }
+
+    /// Writes `block` to the sinks that haven't written it yet.
+    pub fn write_block(&mut self, block: &SinkBlock) -> Result<(), Error> {
+        for (sink, height) in self.sinks.iter_mut() {
+            if height.map_or(false, |height| block.height <= height) {
+                continue;
+            }
+            sink.write_block(block).with_context(|| {
+                format!("sink {} failed at block {}", sink.name(), block.height)
+            })?;
+            *height = Some(block.height);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1200 @@
+use crate::{
+    events::{self, IndexerEvent, EVENTS},
+    models::{
+        AutoIncrement, Balances, ChainBreak, Cursors, Daos, ExtendPledgePeriod, FailedRecords,
+        Input, Output, Profiles, Proposals, SpendingInput, StakeAmounts, StoredRecordOutput, Token,
+        TokenInfos, ViewKeys, Votes,
+    },
+    proto::Records,
+    storage::Storage,
+};
+use anyhow::{anyhow, Error};
+use diesel::{
+    sql_types::{BigInt, Binary, Bool, Nullable, Text},
+    Connection, QueryableByName, RunQueryDsl, SqliteConnection,
+};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use std::collections::BTreeMap;
+
+/// The schema of `migrations` for SQLite, see `migrations_sqlite`.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_sqlite");
+
+const FAILED_RECORDS_COLUMNS: &str = "transition_id, program, function, height, record, error, \
+    attempts, first_failed_at, last_failed_at, next_retry_at, gave_up";
+
+const TOKEN_COLUMNS: &str = "commitment, owner, gates, token_info_id, amount, expires, staked_at, \
+    function, created_at_height, created_by_transition_id, created_at_timestamp, tag, \
+    serial_number, spent_at_height, spent_by_transition_id, private";
+
+/// Opens the SQLite database at `path` (`:memory:` for a private in-memory
+/// one) and brings its schema up to date.
+pub fn establish(path: &str) -> Result<SqliteConnection, Error> {
+    let mut conn = SqliteConnection::establish(path)?;
+
+    diesel::sql_query("PRAGMA foreign_keys = ON").execute(&mut conn)?;
+    diesel::sql_query("PRAGMA busy_timeout = 5000").execute(&mut conn)?;
+    // In-memory databases don't support WAL and silently keep their journal mode.
+    diesel::sql_query("PRAGMA journal_mode = WAL").execute(&mut conn)?;
+
+    conn.run_pending_migrations(MIGRATIONS)
+        .map_err(|err| anyhow!("SQLite migrations failed: {}", err))?;
+
+    Ok(conn)
+}
+
+/// `Proposals` as stored in SQLite, with amounts as decimal text.
+#[derive(QueryableByName)]
+struct SqliteProposal {
+    #[diesel(sql_type = BigInt)]
+    id: i64,
+    #[diesel(sql_type = Text)]
+    title: String,
+    #[diesel(sql_type = Text)]
+    proposer: String,
+    #[diesel(sql_type = Text)]
+    summary: String,
+    #[diesel(sql_type = Text)]
+    body: String,
+    #[diesel(sql_type = BigInt)]
+    dao_id: i64,
+    #[diesel(sql_type = BigInt)]
+    created: i64,
+    #[diesel(sql_type = BigInt)]
+    duration: i64,
+    #[diesel(sql_type = BigInt)]
+    type_: i64,
+    #[diesel(sql_type = Text)]
+    adopt: String,
+    #[diesel(sql_type = Text)]
+    reject: String,
+    #[diesel(sql_type = BigInt)]
+    status: i64,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    updated_at_height: Option<i64>,
+    #[diesel(sql_type = Nullable<Text>)]
+    updated_by_transition_id: Option<String>,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    updated_at_timestamp: Option<i64>,
+}
+
+impl TryFrom<SqliteProposal> for Proposals {
+    type Error = Error;
+
+    fn try_from(proposal: SqliteProposal) -> Result<Self, Self::Error> {
+        Ok(Proposals {
+            id: proposal.id,
+            title: proposal.title,
+            proposer: proposal.proposer,
+            summary: proposal.summary,
+            body: proposal.body,
+            dao_id: proposal.dao_id,
+            created: proposal.created,
+            duration: proposal.duration,
+            type_: proposal.type_,
+            adopt: proposal.adopt.parse()?,
+            reject: proposal.reject.parse()?,
+            status: proposal.status,
+            updated_at_height: proposal.updated_at_height,
+            updated_by_transition_id: proposal.updated_by_transition_id,
+            updated_at_timestamp: proposal.updated_at_timestamp,
+        })
+    }
+}
+
+/// `TokenInfos` as stored in SQLite.
+#[derive(QueryableByName)]
+struct SqliteTokenInfo {
+    #[diesel(sql_type = BigInt)]
+    id: i64,
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Text)]
+    symbol: String,
+    #[diesel(sql_type = Text)]
+    supply: String,
+    #[diesel(sql_type = BigInt)]
+    decimals: i64,
+    #[diesel(sql_type = Text)]
+    max_mint_amount: String,
+    #[diesel(sql_type = Text)]
+    minted_amount: String,
+    #[diesel(sql_type = BigInt)]
+    dao_id: i64,
+    #[diesel(sql_type = Bool)]
+    only_creator_can_mint: bool,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    updated_at_height: Option<i64>,
+    #[diesel(sql_type = Nullable<Text>)]
+    updated_by_transition_id: Option<String>,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    updated_at_timestamp: Option<i64>,
+}
+
+impl TryFrom<SqliteTokenInfo> for TokenInfos {
+    type Error = Error;
+
+    fn try_from(token_info: SqliteTokenInfo) -> Result<Self, Self::Error> {
+        Ok(TokenInfos {
+            id: token_info.id,
+            name: token_info.name,
+            symbol: token_info.symbol,
+            supply: token_info.supply.parse()?,
+            decimals: token_info.decimals,
+            max_mint_amount: token_info.max_mint_amount.parse()?,
+            minted_amount: token_info.minted_amount.parse()?,
+            dao_id: token_info.dao_id,
+            only_creator_can_mint: token_info.only_creator_can_mint,
+            updated_at_height: token_info.updated_at_height,
+            updated_by_transition_id: token_info.updated_by_transition_id,
+            updated_at_timestamp: token_info.updated_at_timestamp,
+        })
+    }
+}
+
+/// `Daos` as stored in SQLite.
+#[derive(QueryableByName)]
+struct SqliteDao {
+    #[diesel(sql_type = BigInt)]
+    id: i64,
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = BigInt)]
+    dao_type: i64,
+    #[diesel(sql_type = Text)]
+    creator: String,
+    #[diesel(sql_type = BigInt)]
+    token_info_id: i64,
+    #[diesel(sql_type = Text)]
+    icon: String,
+    #[diesel(sql_type = Text)]
+    description: String,
+    #[diesel(sql_type = Text)]
+    official_link: String,
+    #[diesel(sql_type = Text)]
+    proposal_count: String,
+    #[diesel(sql_type = Text)]
+    pass_proposal_count: String,
+    #[diesel(sql_type = Text)]
+    vote_count: String,
+    #[diesel(sql_type = Text)]
+    passed_votes_proportion: String,
+    #[diesel(sql_type = Text)]
+    passed_tokens_proportion: String,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    updated_at_height: Option<i64>,
+    #[diesel(sql_type = Nullable<Text>)]
+    updated_by_transition_id: Option<String>,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    updated_at_timestamp: Option<i64>,
+}
+
+impl TryFrom<SqliteDao> for Daos {
+    type Error = Error;
+
+    fn try_from(dao: SqliteDao) -> Result<Self, Self::Error> {
+        Ok(Daos {
+            id: dao.id,
+            name: dao.name,
+            dao_type: dao.dao_type,
+            creator: dao.creator,
+            token_info_id: dao.token_info_id,
+            icon: dao.icon,
+            description: dao.description,
+            official_link: dao.official_link,
+            proposal_count: dao.proposal_count.parse()?,
+            pass_proposal_count: dao.pass_proposal_count.parse()?,
+            vote_count: dao.vote_count.parse()?,
+            passed_votes_proportion: dao.passed_votes_proportion.parse()?,
+            passed_tokens_proportion: dao.passed_tokens_proportion.parse()?,
+            updated_at_height: dao.updated_at_height,
+            updated_by_transition_id: dao.updated_by_transition_id,
+            updated_at_timestamp: dao.updated_at_timestamp,
+        })
+    }
+}
+
+/// `Balances` and `StakeAmounts`, which have the same columns, as stored in
+/// SQLite.
+#[derive(QueryableByName)]
+struct SqliteHolding {
+    #[diesel(sql_type = Text)]
+    key: String,
+    #[diesel(sql_type = Text)]
+    owner: String,
+    #[diesel(sql_type = Text)]
+    amount: String,
+    #[diesel(sql_type = BigInt)]
+    token_info_id: i64,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    updated_at_height: Option<i64>,
+    #[diesel(sql_type = Nullable<Text>)]
+    updated_by_transition_id: Option<String>,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    updated_at_timestamp: Option<i64>,
+}
+
+impl TryFrom<SqliteHolding> for Balances {
+    type Error = Error;
+
+    fn try_from(balance: SqliteHolding) -> Result<Self, Self::Error> {
+        Ok(Balances {
+            key: balance.key,
+            owner: balance.owner,
+            amount: balance.amount.parse()?,
+            token_info_id: balance.token_info_id,
+            updated_at_height: balance.updated_at_height,
+            updated_by_transition_id: balance.updated_by_transition_id,
+            updated_at_timestamp: balance.updated_at_timestamp,
+        })
+    }
+}
+
+impl TryFrom<SqliteHolding> for StakeAmounts {
+    type Error = Error;
+
+    fn try_from(stake: SqliteHolding) -> Result<Self, Self::Error> {
+        Ok(StakeAmounts {
+            key: stake.key,
+            owner: stake.owner,
+            amount: stake.amount.parse()?,
+            token_info_id: stake.token_info_id,
+            updated_at_height: stake.updated_at_height,
+            updated_by_transition_id: stake.updated_by_transition_id,
+            updated_at_timestamp: stake.updated_at_timestamp,
+        })
+    }
+}
+
+/// `Votes` as stored in SQLite.
+#[derive(QueryableByName)]
+struct SqliteVote {
+    #[diesel(sql_type = Text)]
+    key: String,
+    #[diesel(sql_type = Text)]
+    voter: String,
+    #[diesel(sql_type = BigInt)]
+    proposal_id: i64,
+    #[diesel(sql_type = Bool)]
+    is_agreed: bool,
+    #[diesel(sql_type = BigInt)]
+    time: i64,
+    #[diesel(sql_type = Text)]
+    amount: String,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    updated_at_height: Option<i64>,
+    #[diesel(sql_type = Nullable<Text>)]
+    updated_by_transition_id: Option<String>,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    updated_at_timestamp: Option<i64>,
+}
+
+impl TryFrom<SqliteVote> for Votes {
+    type Error = Error;
+
+    fn try_from(vote: SqliteVote) -> Result<Self, Self::Error> {
+        Ok(Votes {
+            key: vote.key,
+            voter: vote.voter,
+            proposal_id: vote.proposal_id,
+            is_agreed: vote.is_agreed,
+            time: vote.time,
+            amount: vote.amount.parse()?,
+            updated_at_height: vote.updated_at_height,
+            updated_by_transition_id: vote.updated_by_transition_id,
+            updated_at_timestamp: vote.updated_at_timestamp,
+        })
+    }
+}
+
+/// `Token` as stored in SQLite.
+#[derive(QueryableByName)]
+struct SqliteToken {
+    #[diesel(sql_type = Text)]
+    commitment: String,
+    #[diesel(sql_type = Text)]
+    owner: String,
+    #[diesel(sql_type = Text)]
+    gates: String,
+    #[diesel(sql_type = BigInt)]
+    token_info_id: i64,
+    #[diesel(sql_type = Text)]
+    amount: String,
+    #[diesel(sql_type = BigInt)]
+    expires: i64,
+    #[diesel(sql_type = BigInt)]
+    staked_at: i64,
+    #[diesel(sql_type = Text)]
+    function: String,
+    #[diesel(sql_type = BigInt)]
+    created_at_height: i64,
+    #[diesel(sql_type = Text)]
+    created_by_transition_id: String,
+    #[diesel(sql_type = BigInt)]
+    created_at_timestamp: i64,
+    #[diesel(sql_type = Nullable<Text>)]
+    tag: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    serial_number: Option<String>,
+    #[diesel(sql_type = Nullable<BigInt>)]
+    spent_at_height: Option<i64>,
+    #[diesel(sql_type = Nullable<Text>)]
+    spent_by_transition_id: Option<String>,
+    #[diesel(sql_type = Bool)]
+    private: bool,
+}
+
+impl TryFrom<SqliteToken> for Token {
+    type Error = Error;
+
+    fn try_from(token: SqliteToken) -> Result<Self, Self::Error> {
+        Ok(Token {
+            commitment: token.commitment,
+            owner: token.owner,
+            gates: token.gates.parse()?,
+            token_info_id: token.token_info_id,
+            amount: token.amount.parse()?,
+            expires: token.expires,
+            staked_at: token.staked_at,
+            function: token.function,
+            created_at_height: token.created_at_height,
+            created_by_transition_id: token.created_by_transition_id,
+            created_at_timestamp: token.created_at_timestamp,
+            tag: token.tag,
+            serial_number: token.serial_number,
+            spent_at_height: token.spent_at_height,
+            spent_by_transition_id: token.spent_by_transition_id,
+            private: token.private,
+        })
+    }
+}
+
+#[derive(QueryableByName)]
+struct SqliteRecordOutput {
+    #[diesel(sql_type = Text)]
+    transition_id: String,
+    #[diesel(sql_type = Text)]
+    function: String,
+    #[diesel(sql_type = BigInt)]
+    height: i64,
+    #[diesel(sql_type = BigInt)]
+    timestamp: i64,
+    #[diesel(sql_type = Text)]
+    id: String,
+    #[diesel(sql_type = Text)]
+    value: String,
+}
+
+#[derive(QueryableByName)]
+struct SqliteSpendingInput {
+    #[diesel(sql_type = Text)]
+    id: String,
+    #[diesel(sql_type = Text)]
+    transition_id: String,
+    #[diesel(sql_type = BigInt)]
+    height: i64,
+}
+
+#[derive(QueryableByName)]
+struct SqliteAutoIncrement {
+    #[diesel(sql_type = BigInt)]
+    key: i64,
+    #[diesel(sql_type = BigInt)]
+    value: i64,
+}
+
+impl Storage for SqliteConnection {
+    fn insert_records(&mut self, records: &Records) -> Result<(), Error> {
+        for record in records.records.iter() {
+            let inputs = record
+                .inputs
+                .iter()
+                .map(|input| Input {
+                    r#type: input.r#type.clone(),
+                    id: input.id.clone(),
+                    value: input.value.clone(),
+                    tag: input.tag.clone(),
+                })
+                .collect::<Vec<Input>>();
+
+            let outputs = record
+                .outputs
+                .iter()
+                .map(|output| Output {
+                    r#type: output.r#type.clone(),
+                    id: output.id.clone(),
+                    checksum: output.checksum.clone(),
+                    value: output.value.clone(),
+                })
+                .collect::<Vec<Output>>();
+
+            diesel::sql_query(
+                "INSERT INTO record (transition_id, program, function, inputs, outputs, \
+                block_hash, previous_hash, transaction_id, network, height, timestamp) \
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+                ON CONFLICT (transition_id) DO NOTHING",
+            )
+            .bind::<Text, _>(&record.transition_id)
+            .bind::<Text, _>(&record.program)
+            .bind::<Text, _>(&record.function)
+            .bind::<Text, _>(serde_json::to_string(&inputs)?)
+            .bind::<Text, _>(serde_json::to_string(&outputs)?)
+            .bind::<Text, _>(&record.block_hash)
+            .bind::<Text, _>(&record.previous_hash)
+            .bind::<Text, _>(&record.transaction_id)
+            .bind::<BigInt, _>(record.network as i64)
+            .bind::<BigInt, _>(record.height as i64)
+            .bind::<BigInt, _>(record.timestamp)
+            .execute(self)?;
+
+            for (position, input) in inputs.iter().enumerate() {
+                diesel::sql_query(
+                    "INSERT INTO record_inputs (transition_id, position, type, id, value, tag) \
+                    VALUES (?, ?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+                )
+                .bind::<Text, _>(&record.transition_id)
+                .bind::<BigInt, _>(position as i64)
+                .bind::<Text, _>(&input.r#type)
+                .bind::<Text, _>(&input.id)
+                .bind::<Text, _>(&input.value)
+                .bind::<Text, _>(&input.tag)
+                .execute(self)?;
+            }
+
+            for (position, output) in outputs.iter().enumerate() {
+                diesel::sql_query(
+                    "INSERT INTO record_outputs (transition_id, position, type, id, checksum, value) \
+                    VALUES (?, ?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+                )
+                .bind::<Text, _>(&record.transition_id)
+                .bind::<BigInt, _>(position as i64)
+                .bind::<Text, _>(&output.r#type)
+                .bind::<Text, _>(&output.id)
+                .bind::<Text, _>(&output.checksum)
+                .bind::<Text, _>(&output.value)
+                .execute(self)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn insert_block(
+        &mut self,
+        height: i64,
+        block_hash: &str,
+        timestamp: i64,
+        records: &Records,
+    ) -> Result<(), Error> {
+        let mut transition_counts: BTreeMap<&str, i64> = BTreeMap::new();
+        for record in records.records.iter() {
+            *transition_counts.entry(&record.transaction_id).or_default() += 1;
+        }
+
+        let first_record = records.records.first();
+        diesel::sql_query(
+            "INSERT INTO blocks (height, block_hash, previous_hash, timestamp, \
+            transaction_count, transition_count) VALUES (?, ?, ?, ?, ?, ?) \
+            ON CONFLICT (height) DO UPDATE SET block_hash = excluded.block_hash, \
+            previous_hash = excluded.previous_hash, timestamp = excluded.timestamp, \
+            transaction_count = excluded.transaction_count, \
+            transition_count = excluded.transition_count",
+        )
+        .bind::<BigInt, _>(height)
+        .bind::<Text, _>(first_record.map_or(block_hash, |record| &record.block_hash))
+        .bind::<Nullable<Text>, _>(first_record.map(|record| record.previous_hash.as_str()))
+        .bind::<BigInt, _>(first_record.map_or(timestamp, |record| record.timestamp))
+        .bind::<BigInt, _>(transition_counts.len() as i64)
+        .bind::<BigInt, _>(records.records.len() as i64)
+        .execute(self)?;
+
+        for (transaction_id, count) in transition_counts.iter() {
+            diesel::sql_query(
+                "INSERT INTO transactions (transaction_id, height, transition_count) \
+                VALUES (?, ?, ?) ON CONFLICT (transaction_id) DO NOTHING",
+            )
+            .bind::<Text, _>(*transaction_id)
+            .bind::<BigInt, _>(height)
+            .bind::<BigInt, _>(*count)
+            .execute(self)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_chain_breaks(
+        &mut self,
+        start_block: i64,
+        end_block: i64,
+    ) -> Result<Vec<ChainBreak>, Error> {
+        let breaks = diesel::sql_query(
+            "SELECT block.height, block.previous_hash, parent.block_hash AS parent_hash \
+            FROM blocks block JOIN blocks parent ON parent.height = block.height - 1 \
+            WHERE block.height BETWEEN ? AND ? AND block.previous_hash <> parent.block_hash \
+            ORDER BY block.height",
+        )
+        .bind::<BigInt, _>(start_block)
+        .bind::<BigInt, _>(end_block)
+        .load(self)?;
+
+        Ok(breaks)
+    }
+
+    fn get_chain_gaps(&mut self, start_block: i64, end_block: i64) -> Result<Vec<i64>, Error> {
+        #[derive(QueryableByName)]
+        struct Gap {
+            #[diesel(sql_type = BigInt)]
+            height: i64,
+        }
+
+        let gaps: Vec<Gap> = diesel::sql_query(
+            "SELECT block.height FROM blocks block \
+            WHERE block.height BETWEEN ? AND ? \
+            AND block.height > (SELECT MIN(height) FROM blocks) \
+            AND NOT EXISTS (SELECT 1 FROM blocks parent WHERE parent.height = block.height - 1) \
+            ORDER BY block.height",
+        )
+        .bind::<BigInt, _>(start_block)
+        .bind::<BigInt, _>(end_block)
+        .load(self)?;
+
+        Ok(gaps.into_iter().map(|gap| gap.height).collect())
+    }
+
+    fn get_block_hash(&mut self, height: i64) -> Result<Option<String>, Error> {
+        #[derive(QueryableByName)]
+        struct BlockHash {
+            #[diesel(sql_type = Text)]
+            block_hash: String,
+        }
+
+        let mut rows: Vec<BlockHash> =
+            diesel::sql_query("SELECT block_hash FROM blocks WHERE height = ?")
+                .bind::<BigInt, _>(height)
+                .load(self)?;
+
+        Ok(rows.pop().map(|row| row.block_hash))
+    }
+
+    fn delete_blocks_from(&mut self, height: i64) -> Result<(), Error> {
+        // Record inputs and outputs, and transactions, are deleted in cascade.
+        for query in [
+            "DELETE FROM token WHERE created_at_height >= ?",
+            "UPDATE token SET serial_number = NULL, spent_at_height = NULL, \
+            spent_by_transition_id = NULL WHERE spent_at_height >= ?",
+            "DELETE FROM failed_records WHERE height >= ?",
+            "DELETE FROM record WHERE height >= ?",
+            "DELETE FROM blocks WHERE height >= ?",
+        ] {
+            diesel::sql_query(query)
+                .bind::<BigInt, _>(height)
+                .execute(self)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_auto_increment_by_key(&mut self, key: i64) -> Result<AutoIncrement, Error> {
+        let mut rows: Vec<SqliteAutoIncrement> =
+            diesel::sql_query("SELECT key, value FROM auto_increment WHERE key = ?")
+                .bind::<BigInt, _>(key)
+                .load(self)?;
+
+        match rows.pop() {
+            Some(row) => Ok(AutoIncrement {
+                key: row.key,
+                value: row.value,
+            }),
+            None => Err(Error::msg("The auto_increment was not found")),
+        }
+    }
+
+    fn upsert_auto_increment(&mut self, auto_increment: AutoIncrement) -> Result<(), Error> {
+        diesel::sql_query(
+            "INSERT INTO auto_increment (key, value) VALUES (?, ?) \
+            ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        )
+        .bind::<BigInt, _>(auto_increment.key)
+        .bind::<BigInt, _>(auto_increment.value)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn create_extend_pledge_period(
+        &mut self,
+        extend_pledge_period: ExtendPledgePeriod,
+    ) -> Result<(), Error> {
+        diesel::sql_query(
+            "INSERT INTO extend_pledge_period (key, value) VALUES (?, ?) \
+            ON CONFLICT (key) DO NOTHING",
+        )
+        .bind::<BigInt, _>(extend_pledge_period.key)
+        .bind::<BigInt, _>(extend_pledge_period.value)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn get_token_info_by_id(&mut self, id: i64) -> Result<TokenInfos, Error> {
+        let mut rows: Vec<SqliteTokenInfo> =
+            diesel::sql_query("SELECT * FROM token_infos WHERE id = ?")
+                .bind::<BigInt, _>(id)
+                .load(self)?;
+
+        match rows.pop() {
+            Some(row) => row.try_into(),
+            None => Err(Error::msg("The token info was not found")),
+        }
+    }
+
+    fn upsert_token_info(&mut self, token_info: TokenInfos) -> Result<(), Error> {
+        diesel::sql_query(
+            "INSERT INTO token_infos (id, name, symbol, supply, decimals, max_mint_amount, \
+            minted_amount, dao_id, only_creator_can_mint, updated_at_height, \
+            updated_by_transition_id, updated_at_timestamp) \
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+            ON CONFLICT (id) DO UPDATE SET name = excluded.name, symbol = excluded.symbol, \
+            supply = excluded.supply, decimals = excluded.decimals, \
+            max_mint_amount = excluded.max_mint_amount, minted_amount = excluded.minted_amount, \
+            dao_id = excluded.dao_id, only_creator_can_mint = excluded.only_creator_can_mint, \
+            updated_at_height = excluded.updated_at_height, \
+            updated_by_transition_id = excluded.updated_by_transition_id, \
+            updated_at_timestamp = excluded.updated_at_timestamp",
+        )
+        .bind::<BigInt, _>(token_info.id)
+        .bind::<Text, _>(token_info.name)
+        .bind::<Text, _>(token_info.symbol)
+        .bind::<Text, _>(token_info.supply.to_string())
+        .bind::<BigInt, _>(token_info.decimals)
+        .bind::<Text, _>(token_info.max_mint_amount.to_string())
+        .bind::<Text, _>(token_info.minted_amount.to_string())
+        .bind::<BigInt, _>(token_info.dao_id)
+        .bind::<Bool, _>(token_info.only_creator_can_mint)
+        .bind::<Nullable<BigInt>, _>(token_info.updated_at_height)
+        .bind::<Nullable<Text>, _>(token_info.updated_by_transition_id)
+        .bind::<Nullable<BigInt>, _>(token_info.updated_at_timestamp)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn update_token_info(&mut self, token_info: TokenInfos) -> Result<(), Error> {
+        diesel::sql_query(
+            "UPDATE token_infos SET name = ?, symbol = ?, supply = ?, decimals = ?, \
+            max_mint_amount = ?, minted_amount = ?, dao_id = ?, only_creator_can_mint = ?, \
+            updated_at_height = ?, updated_by_transition_id = ?, updated_at_timestamp = ? \
+            WHERE id = ?",
+        )
+        .bind::<Text, _>(token_info.name)
+        .bind::<Text, _>(token_info.symbol)
+        .bind::<Text, _>(token_info.supply.to_string())
+        .bind::<BigInt, _>(token_info.decimals)
+        .bind::<Text, _>(token_info.max_mint_amount.to_string())
+        .bind::<Text, _>(token_info.minted_amount.to_string())
+        .bind::<BigInt, _>(token_info.dao_id)
+        .bind::<Bool, _>(token_info.only_creator_can_mint)
+        .bind::<Nullable<BigInt>, _>(token_info.updated_at_height)
+        .bind::<Nullable<Text>, _>(token_info.updated_by_transition_id)
+        .bind::<Nullable<BigInt>, _>(token_info.updated_at_timestamp)
+        .bind::<BigInt, _>(token_info.id)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn get_balance_by_key(&mut self, key: &str) -> Result<Option<Balances>, Error> {
+        let mut rows: Vec<SqliteHolding> =
+            diesel::sql_query("SELECT * FROM balances WHERE key = ?")
+                .bind::<Text, _>(key)
+                .load(self)?;
+
+        rows.pop().map(Balances::try_from).transpose()
+    }
+
+    fn upsert_balances(&mut self, balances: Balances) -> Result<(), Error> {
+        diesel::sql_query(
+            "INSERT INTO balances (key, owner, amount, token_info_id, updated_at_height, \
+            updated_by_transition_id, updated_at_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?) \
+            ON CONFLICT (key) DO UPDATE SET owner = excluded.owner, amount = excluded.amount, \
+            token_info_id = excluded.token_info_id, \
+            updated_at_height = excluded.updated_at_height, \
+            updated_by_transition_id = excluded.updated_by_transition_id, \
+            updated_at_timestamp = excluded.updated_at_timestamp",
+        )
+        .bind::<Text, _>(balances.key)
+        .bind::<Text, _>(balances.owner)
+        .bind::<Text, _>(balances.amount.to_string())
+        .bind::<BigInt, _>(balances.token_info_id)
+        .bind::<Nullable<BigInt>, _>(balances.updated_at_height)
+        .bind::<Nullable<Text>, _>(balances.updated_by_transition_id)
+        .bind::<Nullable<BigInt>, _>(balances.updated_at_timestamp)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn get_stake_amount_by_key(&mut self, key: &str) -> Result<Option<StakeAmounts>, Error> {
+        let mut rows: Vec<SqliteHolding> =
+            diesel::sql_query("SELECT * FROM stake_amounts WHERE key = ?")
+                .bind::<Text, _>(key)
+                .load(self)?;
+
+        rows.pop().map(StakeAmounts::try_from).transpose()
+    }
+
+    fn upsert_stake_amounts(&mut self, stake_amounts: StakeAmounts) -> Result<(), Error> {
+        diesel::sql_query(
+            "INSERT INTO stake_amounts (key, owner, amount, token_info_id, updated_at_height, \
+            updated_by_transition_id, updated_at_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?) \
+            ON CONFLICT (key) DO UPDATE SET owner = excluded.owner, amount = excluded.amount, \
+            token_info_id = excluded.token_info_id, \
+            updated_at_height = excluded.updated_at_height, \
+            updated_by_transition_id = excluded.updated_by_transition_id, \
+            updated_at_timestamp = excluded.updated_at_timestamp",
+        )
+        .bind::<Text, _>(stake_amounts.key)
+        .bind::<Text, _>(stake_amounts.owner)
+        .bind::<Text, _>(stake_amounts.amount.to_string())
+        .bind::<BigInt, _>(stake_amounts.token_info_id)
+        .bind::<Nullable<BigInt>, _>(stake_amounts.updated_at_height)
+        .bind::<Nullable<Text>, _>(stake_amounts.updated_by_transition_id)
+        .bind::<Nullable<BigInt>, _>(stake_amounts.updated_at_timestamp)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn upsert_profile(&mut self, profile: Profiles) -> Result<(), Error> {
+        diesel::sql_query(
+            "INSERT INTO profiles (address, name, avatar, bio, updated_at_height, \
+            updated_by_transition_id, updated_at_timestamp) VALUES (?, ?, ?, ?, ?, ?, ?) \
+            ON CONFLICT (address) DO UPDATE SET name = excluded.name, \
+            avatar = excluded.avatar, bio = excluded.bio, \
+            updated_at_height = excluded.updated_at_height, \
+            updated_by_transition_id = excluded.updated_by_transition_id, \
+            updated_at_timestamp = excluded.updated_at_timestamp",
+        )
+        .bind::<Text, _>(profile.address)
+        .bind::<Text, _>(profile.name)
+        .bind::<Text, _>(profile.avatar)
+        .bind::<Text, _>(profile.bio)
+        .bind::<Nullable<BigInt>, _>(profile.updated_at_height)
+        .bind::<Nullable<Text>, _>(profile.updated_by_transition_id)
+        .bind::<Nullable<BigInt>, _>(profile.updated_at_timestamp)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn get_dao_by_id(&mut self, id: i64) -> Result<Daos, Error> {
+        let mut rows: Vec<SqliteDao> = diesel::sql_query("SELECT * FROM daos WHERE id = ?")
+            .bind::<BigInt, _>(id)
+            .load(self)?;
+
+        match rows.pop() {
+            Some(row) => row.try_into(),
+            None => Err(Error::msg("The dao was not found")),
+        }
+    }
+
+    fn create_dao(&mut self, dao: Daos) -> Result<(), Error> {
+        diesel::sql_query(
+            "INSERT INTO daos (id, name, dao_type, creator, token_info_id, icon, description, \
+            official_link, proposal_count, pass_proposal_count, vote_count, \
+            passed_votes_proportion, passed_tokens_proportion, updated_at_height, \
+            updated_by_transition_id, updated_at_timestamp) \
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+            ON CONFLICT (id) DO NOTHING",
+        )
+        .bind::<BigInt, _>(dao.id)
+        .bind::<Text, _>(dao.name)
+        .bind::<BigInt, _>(dao.dao_type)
+        .bind::<Text, _>(dao.creator)
+        .bind::<BigInt, _>(dao.token_info_id)
+        .bind::<Text, _>(dao.icon)
+        .bind::<Text, _>(dao.description)
+        .bind::<Text, _>(dao.official_link)
+        .bind::<Text, _>(dao.proposal_count.to_string())
+        .bind::<Text, _>(dao.pass_proposal_count.to_string())
+        .bind::<Text, _>(dao.vote_count.to_string())
+        .bind::<Text, _>(dao.passed_votes_proportion.to_string())
+        .bind::<Text, _>(dao.passed_tokens_proportion.to_string())
+        .bind::<Nullable<BigInt>, _>(dao.updated_at_height)
+        .bind::<Nullable<Text>, _>(dao.updated_by_transition_id)
+        .bind::<Nullable<BigInt>, _>(dao.updated_at_timestamp)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn update_dao(&mut self, dao: Daos) -> Result<(), Error> {
+        diesel::sql_query(
+            "UPDATE daos SET name = ?, dao_type = ?, creator = ?, token_info_id = ?, icon = ?, \
+            description = ?, official_link = ?, proposal_count = ?, pass_proposal_count = ?, \
+            vote_count = ?, passed_votes_proportion = ?, passed_tokens_proportion = ?, \
+            updated_at_height = ?, updated_by_transition_id = ?, updated_at_timestamp = ? \
+            WHERE id = ?",
+        )
+        .bind::<Text, _>(dao.name)
+        .bind::<BigInt, _>(dao.dao_type)
+        .bind::<Text, _>(dao.creator)
+        .bind::<BigInt, _>(dao.token_info_id)
+        .bind::<Text, _>(dao.icon)
+        .bind::<Text, _>(dao.description)
+        .bind::<Text, _>(dao.official_link)
+        .bind::<Text, _>(dao.proposal_count.to_string())
+        .bind::<Text, _>(dao.pass_proposal_count.to_string())
+        .bind::<Text, _>(dao.vote_count.to_string())
+        .bind::<Text, _>(dao.passed_votes_proportion.to_string())
+        .bind::<Text, _>(dao.passed_tokens_proportion.to_string())
+        .bind::<Nullable<BigInt>, _>(dao.updated_at_height)
+        .bind::<Nullable<Text>, _>(dao.updated_by_transition_id)
+        .bind::<Nullable<BigInt>, _>(dao.updated_at_timestamp)
+        .bind::<BigInt, _>(dao.id)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn get_proposal_by_id(&mut self, id: i64) -> Result<Option<Proposals>, Error> {
+        let mut rows: Vec<SqliteProposal> = diesel::sql_query(
+            "SELECT id, title, proposer, summary, body, dao_id, created, duration, \
+            type AS type_, adopt, reject, status, updated_at_height, updated_by_transition_id, \
+            updated_at_timestamp FROM proposals WHERE id = ?",
+        )
+        .bind::<BigInt, _>(id)
+        .load(self)?;
+
+        rows.pop().map(Proposals::try_from).transpose()
+    }
+
+    fn create_proposal(&mut self, proposal: Proposals) -> Result<(), Error> {
+        diesel::sql_query(
+            "INSERT INTO proposals (id, title, proposer, summary, body, dao_id, created, \
+            duration, type, adopt, reject, status, updated_at_height, \
+            updated_by_transition_id, updated_at_timestamp) \
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+            ON CONFLICT (id) DO NOTHING",
+        )
+        .bind::<BigInt, _>(proposal.id)
+        .bind::<Text, _>(proposal.title)
+        .bind::<Text, _>(proposal.proposer)
+        .bind::<Text, _>(proposal.summary)
+        .bind::<Text, _>(proposal.body)
+        .bind::<BigInt, _>(proposal.dao_id)
+        .bind::<BigInt, _>(proposal.created)
+        .bind::<BigInt, _>(proposal.duration)
+        .bind::<BigInt, _>(proposal.type_)
+        .bind::<Text, _>(proposal.adopt.to_string())
+        .bind::<Text, _>(proposal.reject.to_string())
+        .bind::<BigInt, _>(proposal.status)
+        .bind::<Nullable<BigInt>, _>(proposal.updated_at_height)
+        .bind::<Nullable<Text>, _>(proposal.updated_by_transition_id)
+        .bind::<Nullable<BigInt>, _>(proposal.updated_at_timestamp)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn update_proposal(&mut self, proposal: Proposals) -> Result<(), Error> {
+        diesel::sql_query(
+            "UPDATE proposals SET title = ?, proposer = ?, summary = ?, body = ?, dao_id = ?, \
+            created = ?, duration = ?, type = ?, adopt = ?, reject = ?, status = ?, \
+            updated_at_height = ?, updated_by_transition_id = ?, updated_at_timestamp = ? \
+            WHERE id = ?",
+        )
+        .bind::<Text, _>(proposal.title)
+        .bind::<Text, _>(proposal.proposer)
+        .bind::<Text, _>(proposal.summary)
+        .bind::<Text, _>(proposal.body)
+        .bind::<BigInt, _>(proposal.dao_id)
+        .bind::<BigInt, _>(proposal.created)
+        .bind::<BigInt, _>(proposal.duration)
+        .bind::<BigInt, _>(proposal.type_)
+        .bind::<Text, _>(proposal.adopt.to_string())
+        .bind::<Text, _>(proposal.reject.to_string())
+        .bind::<BigInt, _>(proposal.status)
+        .bind::<Nullable<BigInt>, _>(proposal.updated_at_height)
+        .bind::<Nullable<Text>, _>(proposal.updated_by_transition_id)
+        .bind::<Nullable<BigInt>, _>(proposal.updated_at_timestamp)
+        .bind::<BigInt, _>(proposal.id)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn get_vote_by_key(&mut self, key: &str) -> Result<Option<Votes>, Error> {
+        let mut rows: Vec<SqliteVote> = diesel::sql_query("SELECT * FROM votes WHERE key = ?")
+            .bind::<Text, _>(key)
+            .load(self)?;
+
+        rows.pop().map(Votes::try_from).transpose()
+    }
+
+    fn insert_votes(&mut self, vote: Votes) -> Result<(), Error> {
+        diesel::sql_query(
+            "INSERT INTO votes (key, voter, proposal_id, is_agreed, time, amount, \
+            updated_at_height, updated_by_transition_id, updated_at_timestamp) \
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) ON CONFLICT (key) DO NOTHING",
+        )
+        .bind::<Text, _>(vote.key)
+        .bind::<Text, _>(vote.voter)
+        .bind::<BigInt, _>(vote.proposal_id)
+        .bind::<Bool, _>(vote.is_agreed)
+        .bind::<BigInt, _>(vote.time)
+        .bind::<Text, _>(vote.amount.to_string())
+        .bind::<Nullable<BigInt>, _>(vote.updated_at_height)
+        .bind::<Nullable<Text>, _>(vote.updated_by_transition_id)
+        .bind::<Nullable<BigInt>, _>(vote.updated_at_timestamp)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn get_token_by_commitment(&mut self, commitment: &str) -> Result<Option<Token>, Error> {
+        let mut rows: Vec<SqliteToken> = diesel::sql_query(format!(
+            "SELECT {} FROM token WHERE commitment = ?",
+            TOKEN_COLUMNS
+        ))
+        .bind::<Text, _>(commitment)
+        .load(self)?;
+
+        rows.pop().map(Token::try_from).transpose()
+    }
+
+    fn insert_token(&mut self, token: &Token) -> Result<(), Error> {
+        diesel::sql_query(format!(
+            "INSERT INTO token ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+            ON CONFLICT (commitment) DO UPDATE SET tag = excluded.tag \
+            WHERE token.tag IS NULL AND excluded.tag IS NOT NULL",
+            TOKEN_COLUMNS
+        ))
+        .bind::<Text, _>(&token.commitment)
+        .bind::<Text, _>(&token.owner)
+        .bind::<Text, _>(token.gates.to_string())
+        .bind::<BigInt, _>(token.token_info_id)
+        .bind::<Text, _>(token.amount.to_string())
+        .bind::<BigInt, _>(token.expires)
+        .bind::<BigInt, _>(token.staked_at)
+        .bind::<Text, _>(&token.function)
+        .bind::<BigInt, _>(token.created_at_height)
+        .bind::<Text, _>(&token.created_by_transition_id)
+        .bind::<BigInt, _>(token.created_at_timestamp)
+        .bind::<Nullable<Text>, _>(&token.tag)
+        .bind::<Nullable<Text>, _>(&token.serial_number)
+        .bind::<Nullable<BigInt>, _>(token.spent_at_height)
+        .bind::<Nullable<Text>, _>(&token.spent_by_transition_id)
+        .bind::<Bool, _>(token.private)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn spend_token(
+        &mut self,
+        tag: &str,
+        serial_number: &str,
+        height: i64,
+        transition_id: &str,
+    ) -> Result<(), Error> {
+        diesel::sql_query(
+            "UPDATE token SET serial_number = ?, spent_at_height = ?, \
+            spent_by_transition_id = ? WHERE tag = ? AND serial_number IS NULL",
+        )
+        .bind::<Text, _>(serial_number)
+        .bind::<BigInt, _>(height)
+        .bind::<Text, _>(transition_id)
+        .bind::<Text, _>(tag)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn get_spending_input(&mut self, tag: &str) -> Result<Option<SpendingInput>, Error> {
+        let mut rows: Vec<SqliteSpendingInput> = diesel::sql_query(
+            "SELECT record_inputs.id, record_inputs.transition_id, record.height \
+            FROM record_inputs JOIN record USING (transition_id) \
+            WHERE record_inputs.tag = ? AND record_inputs.type = 'record' \
+            ORDER BY record.height, record_inputs.transition_id LIMIT 1",
+        )
+        .bind::<Text, _>(tag)
+        .load(self)?;
+
+        Ok(rows.pop().map(|input| SpendingInput {
+            serial_number: input.id,
+            transition_id: input.transition_id,
+            height: input.height,
+        }))
+    }
+
+    fn get_record_outputs_by_program(
+        &mut self,
+        program: &str,
+    ) -> Result<Vec<StoredRecordOutput>, Error> {
+        let rows: Vec<SqliteRecordOutput> = diesel::sql_query(
+            "SELECT record_outputs.transition_id, record.function, record.height, \
+            record.timestamp, record_outputs.id, record_outputs.value \
+            FROM record_outputs JOIN record USING (transition_id) \
+            WHERE record.program = ? AND record_outputs.type = 'record' \
+            ORDER BY record.height, record_outputs.transition_id, record_outputs.position",
+        )
+        .bind::<Text, _>(program)
+        .load(self)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|output| StoredRecordOutput {
+                transition_id: output.transition_id,
+                function: output.function,
+                height: output.height,
+                timestamp: output.timestamp,
+                commitment: output.id,
+                value: output.value,
+            })
+            .collect())
+    }
+
+    fn get_view_keys(&mut self) -> Result<Vec<ViewKeys>, Error> {
+        let rows = diesel::sql_query(
+            "SELECT address, encrypted_view_key, access_token_hash, registered_at, \
+            backfilled_at_height FROM view_keys ORDER BY address",
+        )
+        .load(self)?;
+
+        Ok(rows)
+    }
+
+    fn upsert_view_key(&mut self, view_key: &ViewKeys) -> Result<(), Error> {
+        diesel::sql_query(
+            "INSERT INTO view_keys (address, encrypted_view_key, access_token_hash, \
+            registered_at, backfilled_at_height) VALUES (?, ?, ?, ?, ?) \
+            ON CONFLICT (address) DO UPDATE SET \
+            encrypted_view_key = excluded.encrypted_view_key, \
+            access_token_hash = excluded.access_token_hash",
+        )
+        .bind::<Text, _>(&view_key.address)
+        .bind::<Binary, _>(&view_key.encrypted_view_key)
+        .bind::<Text, _>(&view_key.access_token_hash)
+        .bind::<BigInt, _>(view_key.registered_at)
+        .bind::<Nullable<BigInt>, _>(view_key.backfilled_at_height)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn set_view_key_backfilled(&mut self, address: &str, height: i64) -> Result<(), Error> {
+        diesel::sql_query("UPDATE view_keys SET backfilled_at_height = ? WHERE address = ?")
+            .bind::<BigInt, _>(height)
+            .bind::<Text, _>(address)
+            .execute(self)?;
+
+        Ok(())
+    }
+
+    fn publish_event(&mut self, event: IndexerEvent) {
+        // There is no `serve` process on another database to notify, only
+        // subscribers of this process.
+        if let Some(event) = events::queue_event(event) {
+            let _ = EVENTS.send(event);
+        }
+    }
+
+    fn get_cursor(&mut self, name: &str) -> Result<Option<Cursors>, Error> {
+        let mut rows: Vec<Cursors> = diesel::sql_query(
+            "SELECT name, cursor, height, block_hash FROM cursors WHERE name = ?",
+        )
+        .bind::<Text, _>(name)
+        .load(self)?;
+
+        Ok(rows.pop())
+    }
+
+    fn set_cursor(&mut self, cursor: &Cursors) -> Result<(), Error> {
+        diesel::sql_query(
+            "INSERT INTO cursors (name, cursor, height, block_hash) VALUES (?, ?, ?, ?) \
+            ON CONFLICT (name) DO UPDATE SET cursor = excluded.cursor, \
+            height = excluded.height, block_hash = excluded.block_hash",
+        )
+        .bind::<Text, _>(&cursor.name)
+        .bind::<Text, _>(&cursor.cursor)
+        .bind::<BigInt, _>(cursor.height)
+        .bind::<Text, _>(&cursor.block_hash)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn get_failed_records(
+        &mut self,
+        due_at: i64,
+        limit: i64,
+        include_gave_up: bool,
+    ) -> Result<Vec<FailedRecords>, Error> {
+        let rows = diesel::sql_query(format!(
+            "SELECT {} FROM failed_records WHERE next_retry_at <= ? AND (? OR NOT gave_up) \
+            ORDER BY height, transition_id LIMIT ?",
+            FAILED_RECORDS_COLUMNS
+        ))
+        .bind::<BigInt, _>(due_at)
+        .bind::<Bool, _>(include_gave_up)
+        .bind::<BigInt, _>(limit)
+        .load(self)?;
+
+        Ok(rows)
+    }
+
+    fn get_failed_record(&mut self, transition_id: &str) -> Result<Option<FailedRecords>, Error> {
+        let mut rows: Vec<FailedRecords> = diesel::sql_query(format!(
+            "SELECT {} FROM failed_records WHERE transition_id = ?",
+            FAILED_RECORDS_COLUMNS
+        ))
+        .bind::<Text, _>(transition_id)
+        .load(self)?;
+
+        Ok(rows.pop())
+    }
+
+    fn upsert_failed_record(&mut self, failed_record: &FailedRecords) -> Result<(), Error> {
+        diesel::sql_query(format!(
+            "INSERT INTO failed_records ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+            ON CONFLICT (transition_id) DO UPDATE SET record = excluded.record, \
+            error = excluded.error, attempts = excluded.attempts, \
+            last_failed_at = excluded.last_failed_at, next_retry_at = excluded.next_retry_at, \
+            gave_up = excluded.gave_up",
+            FAILED_RECORDS_COLUMNS
+        ))
+        .bind::<Text, _>(&failed_record.transition_id)
+        .bind::<Text, _>(&failed_record.program)
+        .bind::<Text, _>(&failed_record.function)
+        .bind::<BigInt, _>(failed_record.height)
+        .bind::<Binary, _>(&failed_record.record)
+        .bind::<Text, _>(&failed_record.error)
+        .bind::<BigInt, _>(failed_record.attempts)
+        .bind::<BigInt, _>(failed_record.first_failed_at)
+        .bind::<BigInt, _>(failed_record.last_failed_at)
+        .bind::<BigInt, _>(failed_record.next_retry_at)
+        .bind::<Bool, _>(failed_record.gave_up)
+        .execute(self)?;
+
+        Ok(())
+    }
+
+    fn delete_failed_record(&mut self, transition_id: &str) -> Result<(), Error> {
+        diesel::sql_query("DELETE FROM failed_records WHERE transition_id = ?")
+            .bind::<Text, _>(transition_id)
+            .execute(self)?;
+
+        Ok(())
+    }
+
+    fn transaction(
+        &mut self,
+        f: &mut dyn FnMut(&mut dyn Storage) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let events = events::hold_events(|| Connection::transaction(self, |conn| f(conn)))?;
+        for event in events {
+            self.publish_event(event);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{
+    database::{get_block_by_height, get_cursor_by_name, get_latest_block, MIGRATIONS, POOL},
+    models::RespError,
+    sink::DATABASE_SINK_NAME,
+};
+use anyhow::{anyhow, Error};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use diesel::{sql_query, RunQueryDsl};
+use diesel_migrations::MigrationHarness;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::{ops::DerefMut, sync::RwLock, time::Duration};
+use utoipa::ToSchema;
+
+/// How long `/readyz` waits for a database connection.
+const READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    /// Progress of the `sync` task running in this process, if any.
+    static ref SYNC_STATUS: RwLock<SyncStatus> = RwLock::new(SyncStatus::default());
+}
+
+#[derive(Clone, Default, Serialize, ToSchema)]
+pub struct SyncStatus {
+    /// Whether `sync` runs in this process. When it doesn't, the last block
+    /// is read from the database and the other fields are unknown.
+    pub syncing: bool,
+    pub last_block: Option<i64>,
+    pub last_block_hash: Option<String>,
+    pub last_block_timestamp: Option<i64>,
+    pub cursor: Option<String>,
+    /// Highest block known from Substreams progress or the Aleo REST API.
+    pub head_block: Option<i64>,
+    pub lag_blocks: Option<i64>,
+    pub lag_seconds: Option<i64>,
+    pub last_error: Option<SyncError>,
+}
+
+#[derive(Clone, Serialize, ToSchema)]
+pub struct SyncError {
+    pub message: String,
+    pub timestamp: i64,
+}
+
+/// Answer of `/healthz` and `/readyz`.
+#[derive(Serialize, ToSchema)]
+pub struct RespProbe {
+    /// `ok`, `ready` or `unavailable`
+    pub status: String,
+    /// Why the service is unavailable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RespProbe {
+    fn new(status: &str, error: Option<String>) -> Json<Self> {
+        Json(RespProbe {
+            status: status.to_string(),
+            error,
+        })
+    }
+}
+
+pub fn sync_started() {
+    SYNC_STATUS.write().unwrap().syncing = true;
+}
+
+pub fn block_processed(height: i64, block_hash: &str, timestamp: i64, cursor: &str) {
+    let mut status = SYNC_STATUS.write().unwrap();
+    status.last_block = Some(height);
+    status.last_block_hash = Some(block_hash.to_string());
+    status.last_block_timestamp = Some(timestamp);
+    status.cursor = Some(cursor.to_string());
+}
+
+pub fn head_block_seen(height: i64) {
+    let mut status = SYNC_STATUS.write().unwrap();
+    status.head_block = Some(status.head_block.map_or(height, |head| head.max(height)));
+}
+
+pub fn record_error(message: impl ToString) {
+    SYNC_STATUS.write().unwrap().last_error = Some(SyncError {
+        message: message.to_string(),
+        timestamp: Utc::now().timestamp(),
+    });
+}
+
+impl SyncStatus {
+    fn with_lag(mut self) -> Self {
+        if let (Some(head), Some(last)) = (self.head_block, self.last_block) {
+            self.lag_blocks = Some((head - last).max(0));
+        }
+        self.lag_seconds = self
+            .last_block_timestamp
+            .map(|timestamp| (Utc::now().timestamp() - timestamp).max(0));
+        self
+    }
+}
+
+/// The last block written by the database sink of a `sync` running elsewhere.
+fn indexed_status() -> Result<SyncStatus, Error> {
+    let mut conn = POOL.get()?;
+    let mut status = SyncStatus::default();
+
+    match get_cursor_by_name(&mut conn, DATABASE_SINK_NAME)? {
+        Some(cursor) => {
+            let block = get_block_by_height(&mut conn, cursor.height)?;
+            status.last_block = Some(cursor.height);
+            status.last_block_hash = Some(cursor.block_hash);
+            status.last_block_timestamp = block.map(|block| block.timestamp);
+            status.cursor = Some(cursor.cursor);
+        }
+        None => {
+            if let Some(block) = get_latest_block(&mut conn)? {
+                status.last_block = Some(block.height);
+                status.last_block_hash = Some(block.block_hash);
+                status.last_block_timestamp = Some(block.timestamp);
+            }
+        }
+    }
+
+    Ok(status)
+}
+
+/// Fails unless the database answers and all migrations are applied.
+fn check_ready() -> Result<(), Error> {
+    let mut conn = POOL.get_timeout(READY_TIMEOUT)?;
+    sql_query("SELECT 1").execute(&mut conn)?;
+
+    let pending = conn
+        .deref_mut()
+        .pending_migrations(MIGRATIONS)
+        .map_err(|err| anyhow!("{}", err))?;
+    if !pending.is_empty() {
+        return Err(anyhow!(
+            "{} migrations are not applied, the first is {}",
+            pending.len(),
+            pending[0].name()
+        ));
+    }
+
+    Ok(())
+}
+
+/// The process is up.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "operations",
+    responses((status = 200, description = "The process is up", body = RespProbe))
+)]
+pub async fn healthz_handler() -> Json<RespProbe> {
+    RespProbe::new("ok", None)
+}
+
+/// The database is reachable and migrated.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "operations",
+    responses(
+        (status = 200, description = "The database is reachable and migrated", body = RespProbe),
+        (status = 503, description = "The database is unreachable or has pending migrations", body = RespProbe),
+    )
+)]
+pub async fn readyz_handler() -> (StatusCode, Json<RespProbe>) {
+    match tokio::task::spawn_blocking(check_ready).await {
+        Ok(Ok(())) => (StatusCode::OK, RespProbe::new("ready", None)),
+        Ok(Err(err)) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            RespProbe::new("unavailable", Some(format!("{:#}", err))),
+        ),
+        Err(err) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            RespProbe::new("unavailable", Some(err.to_string())),
+        ),
+    }
+}
+
+/// Progress of the sync, from the `sync` task of this process or else from
+/// the database.
+#[utoipa::path(
+    get,
+    path = "/status",
+    tag = "operations",
+    responses(
+        (status = 200, description = "Last block indexed and lag behind the chain head", body = SyncStatus),
+        (status = 503, description = "The database is unreachable", body = RespError),
+    )
+)]
+pub async fn status_handler() -> Response {
+    let status = SYNC_STATUS.read().unwrap().clone();
+    if status.syncing {
+        return Json(status.with_lag()).into_response();
+    }
+
+    let error = match tokio::task::spawn_blocking(indexed_status).await {
+        Ok(Ok(status)) => return Json(status.with_lag()).into_response(),
+        Ok(Err(err)) => format!("{:#}", err),
+        Err(err) => err.to_string(),
+    };
+    (StatusCode::SERVICE_UNAVAILABLE, Json(RespError { error })).into_response()
+}
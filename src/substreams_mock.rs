@@ -0,0 +1,196 @@
+use crate::proto::{
+    self,
+    module_output::Data as ModuleOutputData,
+    module_progress,
+    stream_server::{Stream, StreamServer},
+    BlockRange, BlockScopedData, Clock, ForkStep, ModuleOutput, ModuleProgress, ModulesProgress,
+    Records, Response,
+};
+use async_stream::stream;
+use futures03::stream::{self as futures_stream, BoxStream};
+use prost::Message;
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use tokio::{net::TcpListener, task::JoinHandle};
+use tonic::{transport::Server, Request, Status};
+
+pub const MODULE_NAME: &str = "map_records";
+
+/// What the server does with one `Blocks` request.
+pub enum Session {
+    /// Fails the request, like an endpoint that is down.
+    Refuse(Status),
+    /// Sends the responses in order, then closes the stream. An `Err` closes
+    /// it with that status instead, like a dropped connection.
+    Stream(Vec<Result<Response, Status>>),
+}
+
+/// A `Blocks` request received by the server.
+#[derive(Clone)]
+pub struct Connection {
+    pub request: proto::Request,
+    pub at: Instant,
+}
+
+struct MockSubstreams {
+    sessions: Mutex<VecDeque<Session>>,
+    connections: Arc<Mutex<Vec<Connection>>>,
+}
+
+#[tonic::async_trait]
+impl Stream for MockSubstreams {
+    type BlocksStream = BoxStream<'static, Result<Response, Status>>;
+
+    async fn blocks(
+        &self,
+        request: Request<proto::Request>,
+    ) -> Result<tonic::Response<Self::BlocksStream>, Status> {
+        self.connections.lock().unwrap().push(Connection {
+            request: request.into_inner(),
+            at: Instant::now(),
+        });
+
+        // Once the script is over the server behaves as if it were down.
+        let session = self
+            .sessions
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Session::Refuse(Status::unavailable("no more sessions")));
+        match session {
+            Session::Refuse(status) => Err(status),
+            Session::Stream(responses) => Ok(tonic::Response::new(Box::pin(futures_stream::iter(
+                responses,
+            )))),
+        }
+    }
+}
+
+/// In-process Substreams server replaying scripted sessions, to exercise
+/// `SubstreamsStream` without a live firehose-aleo. It listens on a local
+/// port until dropped.
+pub struct MockServer {
+    pub url: String,
+    connections: Arc<Mutex<Vec<Connection>>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockServer {
+    pub async fn start(sessions: Vec<Session>) -> MockServer {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .await
+            .unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let connections = Arc::new(Mutex::new(Vec::new()));
+        let service = StreamServer::new(MockSubstreams {
+            sessions: Mutex::new(sessions.into()),
+            connections: connections.clone(),
+        });
+        let incoming = stream! {
+            loop {
+                yield listener.accept().await.map(|(socket, _)| socket);
+            }
+        };
+        let handle = tokio::spawn(async move {
+            Server::builder()
+                .add_service(service)
+                .serve_with_incoming(incoming)
+                .await
+                .unwrap();
+        });
+
+        MockServer {
+            url,
+            connections,
+            handle,
+        }
+    }
+
+    /// Requests received so far, in order.
+    pub fn connections(&self) -> Vec<Connection> {
+        self.connections.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Output of `MODULE_NAME` for a block.
+pub fn block(height: u64, cursor: &str, step: ForkStep, records: &Records) -> BlockScopedData {
+    BlockScopedData {
+        outputs: vec![ModuleOutput {
+            name: MODULE_NAME.to_string(),
+            data: Some(ModuleOutputData::MapOutput(prost_types::Any {
+                type_url: "type.googleapis.com/aleo.record.v1.Records".to_string(),
+                value: records.encode_to_vec(),
+            })),
+            ..Default::default()
+        }],
+        clock: Some(Clock {
+            id: format!("block-{}", height),
+            number: height,
+            timestamp: Some(prost_types::Timestamp {
+                seconds: 1_700_000_000 + height as i64,
+                nanos: 0,
+            }),
+        }),
+        step: step as i32,
+        cursor: cursor.to_string(),
+    }
+}
+
+/// A new block without records.
+pub fn data(height: u64, cursor: &str) -> Result<Response, Status> {
+    Ok(Response {
+        message: Some(proto::response::Message::Data(block(
+            height,
+            cursor,
+            ForkStep::StepNew,
+            &Records::default(),
+        ))),
+    })
+}
+
+/// A block removed by a fork.
+pub fn undo(height: u64, cursor: &str) -> Result<Response, Status> {
+    Ok(Response {
+        message: Some(proto::response::Message::Data(block(
+            height,
+            cursor,
+            ForkStep::StepUndo,
+            &Records::default(),
+        ))),
+    })
+}
+
+/// `MODULE_NAME` processed up to `end_block`.
+pub fn progress(end_block: u64) -> Result<Response, Status> {
+    Ok(Response {
+        message: Some(proto::response::Message::Progress(ModulesProgress {
+            modules: vec![ModuleProgress {
+                name: MODULE_NAME.to_string(),
+                r#type: Some(module_progress::Type::ProcessedRanges(
+                    module_progress::ProcessedRange {
+                        processed_ranges: vec![BlockRange {
+                            start_block: 0,
+                            end_block,
+                        }],
+                    },
+                )),
+            }],
+        })),
+    })
+}
+
+/// The connection is lost.
+pub fn disconnect() -> Result<Response, Status> {
+    Err(Status::unavailable("connection reset"))
+}
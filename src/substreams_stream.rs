@@ -9,12 +9,20 @@ use std::{
 };
 use tokio::time::sleep;
 use tokio_retry::strategy::ExponentialBackoff;
+use tracing::{debug, info, warn};
 
 use crate::{
-    proto::{self, ForkStep::*},
+    metrics::STREAM_RECONNECTS,
+    proto::{self, module_progress, ForkStep::*},
+    status,
     substreams::SubstreamsEndpoint,
 };
 
+/// Delay before reconnecting after an error, doubled after every failed
+/// attempt.
+const RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(45);
+
 pub struct SubstreamsStream {
     stream: Pin<Box<dyn Stream<Item = Result<BlockResponse, Error>> + Send>>,
 }
@@ -36,6 +44,7 @@ impl SubstreamsStream {
                 module_name,
                 start_block,
                 end_block,
+                RECONNECT_DELAY,
             )),
         }
     }
@@ -48,8 +57,12 @@ fn stream_blocks(
     module_name: String,
     start_block_num: i64,
     stop_block_num: u64,
+    reconnect_delay: Duration,
 ) -> impl Stream<Item = Result<BlockResponse, Error>> {
     let mut latest_cursor = cursor.unwrap_or_else(|| "".to_string());
+    // Block expected next, to tell the end of the range from a stream that
+    // was closed early.
+    let mut next_block = start_block_num.max(0) as u64;
 
     let request = proto::Request {
         start_block_num,
@@ -63,30 +76,29 @@ fn stream_blocks(
     };
 
     // Back off exponentially whenever we encounter a connection error or a stream with bad data
-    let mut backoff = ExponentialBackoff::from_millis(500).max_delay(Duration::from_secs(45));
-
-    // This attribute is needed because `try_stream!` seems to break detection of `skip_backoff` assignments
-    #[allow(unused_assignments)]
-    let mut skip_backoff = false;
+    let mut backoff = reconnect_backoff(reconnect_delay);
 
     try_stream! {
         loop {
-            println!("Blockstreams disconnected, connecting (endpoint {}, start block {}, cursor {})",
+            info!("Blockstreams disconnected, connecting (endpoint {}, start block {}, cursor {})",
                 &endpoint,
                 start_block_num,
                 &latest_cursor
             );
 
-            // We just reconnected, assume that we want to back off on errors
-            skip_backoff = false;
-
-            let result = endpoint.clone().substreams(request.clone()).await;
+            // Resume after the last block we yielded, the start block is
+            // ignored when a cursor is given.
+            let request = proto::Request {
+                start_cursor: latest_cursor.clone(),
+                ..request.clone()
+            };
+            let result = endpoint.clone().substreams(request).await;
 
             match result {
                 Ok(stream) => {
-                    println!("Blockstreams connected");
+                    info!("Blockstreams connected");
 
-                    let mut expected_stream_end = stop_block_num != 0;
+                    let mut encountered_error = false;
 
                     for await response in stream{
                         match process_substreams_response(
@@ -97,17 +109,30 @@ fn stream_blocks(
                                     None => {}
                                     Some(block_scoped_data) => {
                                         // Reset backoff because we got a good value from the stream
-                                        backoff = ExponentialBackoff::from_millis(500).max_delay(Duration::from_secs(45));
+                                        backoff = reconnect_backoff(reconnect_delay);
+
+                                        if let Some(clock) = &block_scoped_data.clock {
+                                            next_block = if block_scoped_data.step == StepUndo as i32 {
+                                                clock.number
+                                            } else {
+                                                clock.number + 1
+                                            };
+                                        }
 
                                         let cursor = block_scoped_data.cursor.clone();
-                                        yield BlockResponse::New(block_scoped_data);
+                                        if block_scoped_data.step == StepUndo as i32 {
+                                            yield BlockResponse::Undo(block_scoped_data);
+                                        } else {
+                                            yield BlockResponse::New(block_scoped_data);
+                                        }
 
                                         latest_cursor = cursor;
                                     }
                                 }
                             },
                             Err(err) => {
-                                println!("Received error {:#}", err);
+                                warn!("Received error {:#}", err);
+                                status::record_error(format!("stream: {:#}", err));
 
                                 // We have an open connection but there was an error processing the Firehose
                                 // response. We will reconnect the stream after this; this is the case where
@@ -115,16 +140,18 @@ fn stream_blocks(
                                 // An example of this situation is if we get invalid block or transaction data
                                 // that cannot be decoded properly.
 
-                                expected_stream_end = true;
+                                encountered_error = true;
                                 break;
                             }
                         }
                     }
 
-                    if !expected_stream_end {
-                        println!("Stream blocks complete unexpectedly, expecting stream to always stream blocks");
-                    } else {
-                        return
+                    if !encountered_error {
+                        if stop_block_num != 0 && next_block >= stop_block_num {
+                            info!("Stream reached end block {}", stop_block_num);
+                            return
+                        }
+                        warn!("Stream blocks complete unexpectedly at block {}, expecting stream to always stream blocks", next_block);
                     }
                 },
                 Err(e) => {
@@ -132,22 +159,34 @@ fn stream_blocks(
                     // case where we actually _want_ to back off in case we keep
                     // having connection errors.
 
-                    println!("Unable to connect to endpoint: {:#}", e);
+                    warn!("Unable to connect to endpoint: {:#}", e);
+                    status::record_error(format!("connect: {:#}", e));
                 }
             }
 
-            // If we reach this point, we must wait a bit before retrying, unless `skip_backoff` is true
-            if !skip_backoff {
-                if let Some(duration) = backoff.next() {
-                    sleep(duration).await
-                }
+            // If we reach this point, we must wait a bit before retrying
+            STREAM_RECONNECTS.inc();
+            if let Some(duration) = backoff.next() {
+                sleep(duration).await
             }
         }
     }
 }
 
+/// Delays between reconnections, starting at `delay` and doubling up to
+/// `MAX_RECONNECT_DELAY`.
+fn reconnect_backoff(delay: Duration) -> ExponentialBackoff {
+    // `ExponentialBackoff` raises its base to the power of the attempt, the
+    // factor scales the result.
+    ExponentialBackoff::from_millis(2)
+        .factor(delay.as_millis() as u64 / 2)
+        .max_delay(MAX_RECONNECT_DELAY)
+}
+
 pub enum BlockResponse {
     New(proto::BlockScopedData),
+    /// The block of the data was removed by a fork, with every block after it.
+    Undo(proto::BlockScopedData),
 }
 
 async fn process_substreams_response(
@@ -160,14 +199,41 @@ async fn process_substreams_response(
 
     match response.message {
         Some(proto::response::Message::Data(block_scoped_data)) => Ok(Some(block_scoped_data)),
+        Some(proto::response::Message::Progress(progress)) => {
+            track_progress(&progress);
+            Ok(None)
+        }
         None => {
-            println!("Got None on substream message");
+            debug!("Got None on substream message");
             Ok(None)
         }
         _ => Ok(None),
     }
 }
 
+/// Reports how far Substreams has processed, and module failures.
+fn track_progress(progress: &proto::ModulesProgress) {
+    for module in progress.modules.iter() {
+        match &module.r#type {
+            Some(module_progress::Type::ProcessedRanges(ranges)) => {
+                if let Some(end_block) = ranges
+                    .processed_ranges
+                    .iter()
+                    .map(|range| range.end_block)
+                    .max()
+                {
+                    status::head_block_seen(end_block as i64);
+                }
+            }
+            Some(module_progress::Type::Failed(failed)) => {
+                warn!("Module {} failed: {}", module.name, failed.reason);
+                status::record_error(format!("module {}: {}", module.name, failed.reason));
+            }
+            _ => {}
+        }
+    }
+}
+
 impl Stream for SubstreamsStream {
     type Item = Result<BlockResponse, Error>;
 
@@ -175,3 +241,164 @@ impl Stream for SubstreamsStream {
         self.stream.poll_next_unpin(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::substreams_mock::{
+        data, disconnect, progress, undo, Connection, MockServer, Session, MODULE_NAME,
+    };
+    use tokio::time::timeout;
+    use tonic::Status;
+
+    const DELAY: Duration = Duration::from_millis(50);
+
+    /// Streams `start_block..stop_block` from the server and collects the
+    /// height, whether it is undone and cursor of every block until the
+    /// stream ends.
+    async fn collect(
+        server: &MockServer,
+        cursor: Option<&str>,
+        start_block: i64,
+        stop_block: u64,
+    ) -> Vec<(u64, bool, String)> {
+        let endpoint = Arc::new(SubstreamsEndpoint::new(&server.url, None).await.unwrap());
+        let stream = stream_blocks(
+            endpoint,
+            cursor.map(str::to_string),
+            None,
+            MODULE_NAME.to_string(),
+            start_block,
+            stop_block,
+            DELAY,
+        );
+
+        let blocks = timeout(Duration::from_secs(10), stream.collect::<Vec<_>>())
+            .await
+            .expect("the stream should end");
+        blocks
+            .into_iter()
+            .map(|block| match block.unwrap() {
+                BlockResponse::New(data) => (data.clock.unwrap().number, false, data.cursor),
+                BlockResponse::Undo(data) => (data.clock.unwrap().number, true, data.cursor),
+            })
+            .collect()
+    }
+
+    fn cursors(connections: &[Connection]) -> Vec<&str> {
+        connections
+            .iter()
+            .map(|connection| connection.request.start_cursor.as_str())
+            .collect()
+    }
+
+    fn heights(blocks: &[(u64, bool, String)]) -> Vec<u64> {
+        blocks.iter().map(|(height, _, _)| *height).collect()
+    }
+
+    #[tokio::test]
+    async fn ends_at_stop_block() {
+        let server = MockServer::start(vec![Session::Stream(vec![
+            progress(12),
+            data(10, "c10"),
+            data(11, "c11"),
+        ])])
+        .await;
+
+        let blocks = collect(&server, None, 10, 12).await;
+
+        assert_eq!(heights(&blocks), vec![10, 11]);
+        assert_eq!(blocks[1].2, "c11");
+        let connections = server.connections();
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].request.start_block_num, 10);
+        assert_eq!(connections[0].request.stop_block_num, 12);
+        assert_eq!(connections[0].request.output_modules, vec![MODULE_NAME]);
+    }
+
+    #[tokio::test]
+    async fn resumes_from_latest_cursor_after_disconnect() {
+        let server = MockServer::start(vec![
+            Session::Stream(vec![data(10, "c10"), data(11, "c11"), disconnect()]),
+            Session::Stream(vec![data(12, "c12")]),
+        ])
+        .await;
+
+        let blocks = collect(&server, Some("c9"), 10, 13).await;
+
+        assert_eq!(heights(&blocks), vec![10, 11, 12]);
+        assert_eq!(cursors(&server.connections()), vec!["c9", "c11"]);
+    }
+
+    #[tokio::test]
+    async fn reconnects_when_stream_closes_before_stop_block() {
+        let server = MockServer::start(vec![
+            Session::Stream(vec![data(10, "c10")]),
+            Session::Stream(vec![]),
+            Session::Stream(vec![data(11, "c11")]),
+        ])
+        .await;
+
+        let blocks = collect(&server, None, 10, 12).await;
+
+        assert_eq!(heights(&blocks), vec![10, 11]);
+        assert_eq!(cursors(&server.connections()), vec!["", "c10", "c10"]);
+    }
+
+    #[tokio::test]
+    async fn backs_off_between_failed_connections() {
+        let server = MockServer::start(vec![
+            Session::Refuse(Status::unavailable("down")),
+            Session::Refuse(Status::internal("failing")),
+            Session::Stream(vec![data(10, "c10"), disconnect()]),
+            Session::Stream(vec![data(11, "c11")]),
+        ])
+        .await;
+
+        let blocks = collect(&server, None, 10, 12).await;
+
+        assert_eq!(heights(&blocks), vec![10, 11]);
+        let connections = server.connections();
+        assert_eq!(cursors(&connections), vec!["", "", "", "c10"]);
+        let gaps: Vec<Duration> = connections
+            .windows(2)
+            .map(|pair| pair[1].at - pair[0].at)
+            .collect();
+        // The delay doubles while connecting fails, and starts over once a
+        // block is received.
+        assert!(gaps[0] >= DELAY, "{:?}", gaps);
+        assert!(gaps[1] >= DELAY * 2, "{:?}", gaps);
+        assert!(gaps[2] < DELAY * 4, "{:?}", gaps);
+        assert!(gaps[2] >= DELAY, "{:?}", gaps);
+    }
+
+    #[tokio::test]
+    async fn yields_undo_steps_apart() {
+        let server = MockServer::start(vec![Session::Stream(vec![
+            data(10, "c10"),
+            data(11, "c11"),
+            undo(11, "c11-undo"),
+            data(11, "c11-fork"),
+        ])])
+        .await;
+
+        let blocks = collect(&server, None, 10, 12).await;
+
+        assert_eq!(heights(&blocks), vec![10, 11, 11, 11]);
+        assert_eq!(
+            blocks.iter().map(|block| block.1).collect::<Vec<_>>(),
+            vec![false, false, true, false]
+        );
+        assert_eq!(blocks[3].2, "c11-fork");
+    }
+
+    #[test]
+    fn reconnect_delay_doubles_up_to_max() {
+        let delays: Vec<Duration> = reconnect_backoff(RECONNECT_DELAY).take(9).collect();
+
+        assert_eq!(delays[0], Duration::from_millis(500));
+        assert_eq!(delays[1], Duration::from_secs(1));
+        assert_eq!(delays[2], Duration::from_secs(2));
+        assert_eq!(delays[8], MAX_RECONNECT_DELAY);
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::Error;
+use axum::http::Request;
+use clap::{Args, ValueEnum};
+use opentelemetry::{
+    sdk::{trace, Resource},
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use tracing::{info_span, Span};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+const SERVICE_NAME: &str = "nexus-dao-indexer";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per line, with the fields of the current spans
+    Json,
+}
+
+/// How logs and traces are emitted, common to all commands.
+#[derive(Args, Clone)]
+pub struct TelemetryArgs {
+    /// Log output format, levels are set with `RUST_LOG` (`info` by default)
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// OpenTelemetry collector to export traces to over OTLP/gRPC, e.g. `http://localhost:4317`
+    #[arg(long, global = true)]
+    pub otlp_endpoint: Option<String>,
+}
+
+/// Installs the global subscriber. Must be called from the Tokio runtime
+/// since spans are exported in the background.
+pub fn init(args: &TelemetryArgs) -> Result<(), Error> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+
+    let output = match args.log_format {
+        LogFormat::Text => fmt::layer().boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    };
+
+    let otel = match &args.otlp_endpoint {
+        Some(endpoint) => {
+            let tracer = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(endpoint),
+                )
+                .with_trace_config(trace::config().with_resource(Resource::new(vec![
+                    KeyValue::new("service.name", SERVICE_NAME),
+                ])))
+                .install_batch(opentelemetry::runtime::Tokio)?;
+            Some(tracing_opentelemetry::layer().with_tracer(tracer))
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(output)
+        .with(otel)
+        .try_init()?;
+
+    Ok(())
+}
+
+/// Exports the spans that are still buffered.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// Span of an API request, with the id set by `SetRequestIdLayer`.
+pub fn http_span<B>(request: &Request<B>) -> Span {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    info_span!(
+        "http_request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id,
+    )
+}
@@ -0,0 +1,839 @@
+use crate::{
+    database::{
+        get_balances_by_owner, get_block_by_height, get_creating_dao_proposal_ids,
+        get_dao_proposals_page, get_daos_by_ids, get_daos_page, get_funds_summary,
+        get_latest_block, get_pledgers_by_token_info_id, get_pledgers_summary,
+        get_profiles_by_addresses, get_proposal_votes_page, get_proposals_by_ids,
+        get_proposals_page, get_records_page, get_stake_funds_summary, get_stakes_by_owner,
+        get_token_infos_by_ids, get_token_infos_page, get_tokens_by_owner,
+        get_transactions_by_height, get_view_key_by_address, get_vote_by_key, get_vote_by_voter,
+        get_vote_totals_by_proposal_id, insert_token_info, upsert_profile, upsert_view_key,
+        with_conn,
+    },
+    handlers::resp_record,
+    models::{
+        Balances, Blocks, Daos, Profiles, Proposals, Provenance, RecordFilter, ReqProfile,
+        ReqViewKey, RespBlock, RespError, RespProposalVotes, RespRecords, RespStats,
+        RespTokenHolding, RespTokenPledgers, RespViewKey, RespVoterVote, StakeAmounts, Token,
+        TokenInfos, ViewKeys, Votes,
+    },
+    pagination::{paginate, Pagination},
+    program_handler::bhp256_hash_address,
+    view_keys,
+};
+use axum::{
+    extract::{Path, Query, RawQuery},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use diesel::{r2d2::ConnectionManager, PgConnection};
+use r2d2::PooledConnection;
+use serde::Deserialize;
+use snarkvm::prelude::{Testnet3, ViewKey};
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
+use tracing::error;
+use utoipa::IntoParams;
+
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl ToString) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn not_found(message: impl ToString) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn unauthorized(message: impl ToString) -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn unavailable(message: impl ToString) -> Self {
+        Self {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl<E: Into<anyhow::Error>> From<E> for ApiError {
+    fn from(err: E) -> Self {
+        let err: anyhow::Error = err.into();
+        error!("Request error {:#}", err);
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: "internal error".to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(RespError {
+                error: self.message,
+            }),
+        )
+            .into_response()
+    }
+}
+
+/// Reads an id list given either comma separated (`?ids=1,2`) or repeated
+/// (`?ids=1&ids=2`). Returns `None` when the parameter is absent.
+fn parse_ids(query: &Option<String>, name: &str) -> Result<Option<Vec<i64>>, ApiError> {
+    let query = match query {
+        Some(query) => query,
+        None => return Ok(None),
+    };
+    let pairs: Vec<(String, String)> =
+        serde_urlencoded::from_str(query).map_err(ApiError::bad_request)?;
+
+    let mut ids: Option<Vec<i64>> = None;
+    for (_, value) in pairs.iter().filter(|(key, _)| key == name) {
+        let ids = ids.get_or_insert_with(Vec::new);
+        for id in value.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+            let id = id
+                .parse::<i64>()
+                .map_err(|_| ApiError::bad_request(format!("invalid id {} in {}", id, name)))?;
+            ids.push(id);
+        }
+    }
+    Ok(ids)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProposalFilter {
+    /// Only return pending proposals creating a new DAO
+    pub creating_dao: Option<bool>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProvenanceParam {
+    /// Include `updated_at_height`, `updated_by_transition_id` and
+    /// `updated_at_timestamp`, the transition that last wrote each row
+    pub provenance: Option<bool>,
+}
+
+impl ProvenanceParam {
+    fn apply<T: Provenance>(&self, mut rows: T) -> T {
+        if !self.provenance.unwrap_or(false) {
+            rows.clear_provenance();
+        }
+        rows
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SpentParam {
+    /// Include the records already spent
+    pub include_spent: Option<bool>,
+}
+
+/// Checks the `Authorization: Bearer <token>` header against the access token
+/// given at the registration of the view key of `address`.
+async fn authenticate(headers: &HeaderMap, address: &str) -> Result<(), ApiError> {
+    let access_token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| ApiError::unauthorized("missing bearer token"))?;
+    let access_token_hash = view_keys::hash_access_token(access_token.trim());
+
+    let address = address.to_string();
+    match with_conn(move |conn| get_view_key_by_address(conn, &address)).await? {
+        Some(view_key) if view_key.access_token_hash == access_token_hash => Ok(()),
+        _ => Err(ApiError::unauthorized("invalid access token")),
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BlockRange {
+    /// First block height, inclusive
+    pub start_block: Option<i64>,
+    /// Last block height, inclusive
+    pub end_block: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/daos",
+    tag = "daos",
+    params(
+        ("ids" = Option<String>, Query, description = "Comma separated or repeated DAO ids, pagination is ignored when set"),
+        Pagination, ProvenanceParam,
+    ),
+    responses(
+        (status = 200, description = "DAOs ordered by id, unknown ids are skipped", body = [Daos]),
+        (status = 400, description = "Invalid parameters", body = RespError),
+    )
+)]
+pub async fn list_daos_handler(
+    Query(pagination): Query<Pagination>,
+    Query(provenance): Query<ProvenanceParam>,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Vec<Daos>>, ApiError> {
+    let ids = parse_ids(&query, "ids")?;
+    let (offset, limit) = pagination.window();
+
+    let daos = with_conn(move |conn| match ids {
+        Some(ids) => {
+            let mut daos = get_daos_by_ids(conn, &ids)?;
+            daos.sort_by_key(|dao| dao.id);
+            Ok(daos)
+        }
+        None => get_daos_page(conn, offset, limit),
+    })
+    .await?;
+
+    Ok(Json(provenance.apply(daos)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/daos/{id}",
+    tag = "daos",
+    params(("id" = i64, Path, description = "DAO id"), ProvenanceParam),
+    responses(
+        (status = 200, description = "The DAO", body = Daos),
+        (status = 404, description = "Unknown DAO", body = RespError),
+    )
+)]
+pub async fn get_dao_handler(
+    Path(id): Path<i64>,
+    Query(provenance): Query<ProvenanceParam>,
+) -> Result<Json<Daos>, ApiError> {
+    with_conn(move |conn| get_daos_by_ids(conn, &[id]))
+        .await?
+        .pop()
+        .map(|row| Json(provenance.apply(row)))
+        .ok_or_else(|| ApiError::not_found(format!("dao {} not found", id)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/daos/{id}/proposals",
+    tag = "proposals",
+    params(("id" = i64, Path, description = "DAO id"), Pagination, ProvenanceParam),
+    responses(
+        (status = 200, description = "Proposals of the DAO ordered by id", body = [Proposals]),
+    )
+)]
+pub async fn list_dao_proposals_handler(
+    Path(id): Path<i64>,
+    Query(pagination): Query<Pagination>,
+    Query(provenance): Query<ProvenanceParam>,
+) -> Result<Json<Vec<Proposals>>, ApiError> {
+    let (offset, limit) = pagination.window();
+
+    let proposals = with_conn(move |conn| get_dao_proposals_page(conn, id, offset, limit)).await?;
+
+    Ok(Json(provenance.apply(proposals)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/proposals",
+    tag = "proposals",
+    params(
+        ("ids" = Option<String>, Query, description = "Comma separated or repeated proposal ids, pagination is ignored when set"),
+        ProposalFilter,
+        Pagination, ProvenanceParam,
+    ),
+    responses(
+        (status = 200, description = "Proposals ordered by id, unknown ids are skipped", body = [Proposals]),
+        (status = 400, description = "Invalid parameters", body = RespError),
+    )
+)]
+pub async fn list_proposals_handler(
+    Query(filter): Query<ProposalFilter>,
+    Query(pagination): Query<Pagination>,
+    Query(provenance): Query<ProvenanceParam>,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Vec<Proposals>>, ApiError> {
+    let mut ids = parse_ids(&query, "ids")?;
+    let creating_dao = filter.creating_dao.unwrap_or(false);
+
+    let proposals = with_conn(move |conn| {
+        if creating_dao {
+            let creating_dao_ids = get_creating_dao_proposal_ids(conn)?;
+            ids = Some(match ids {
+                Some(ids) => ids
+                    .into_iter()
+                    .filter(|id| creating_dao_ids.contains(id))
+                    .collect(),
+                None => paginate(creating_dao_ids, pagination.offset, pagination.limit),
+            });
+        }
+
+        match ids {
+            Some(ids) => {
+                let mut proposals = get_proposals_by_ids(conn, &ids)?;
+                proposals.sort_by_key(|proposal| proposal.id);
+                Ok(proposals)
+            }
+            None => {
+                let (offset, limit) = pagination.window();
+                get_proposals_page(conn, offset, limit)
+            }
+        }
+    })
+    .await?;
+
+    Ok(Json(provenance.apply(proposals)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/proposals/{id}",
+    tag = "proposals",
+    params(("id" = i64, Path, description = "Proposal id"), ProvenanceParam),
+    responses(
+        (status = 200, description = "The proposal", body = Proposals),
+        (status = 404, description = "Unknown proposal", body = RespError),
+    )
+)]
+pub async fn get_proposal_handler(
+    Path(id): Path<i64>,
+    Query(provenance): Query<ProvenanceParam>,
+) -> Result<Json<Proposals>, ApiError> {
+    with_conn(move |conn| get_proposals_by_ids(conn, &[id]))
+        .await?
+        .pop()
+        .map(|row| Json(provenance.apply(row)))
+        .ok_or_else(|| ApiError::not_found(format!("proposal {} not found", id)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/proposals/{id}/votes",
+    tag = "votes",
+    params(("id" = i64, Path, description = "Proposal id"), Pagination, ProvenanceParam),
+    responses(
+        (status = 200, description = "Vote totals of the proposal and a page of its votes ordered by time", body = RespProposalVotes),
+        (status = 404, description = "Unknown proposal", body = RespError),
+    )
+)]
+pub async fn list_proposal_votes_handler(
+    Path(id): Path<i64>,
+    Query(pagination): Query<Pagination>,
+    Query(provenance): Query<ProvenanceParam>,
+) -> Result<Json<RespProposalVotes>, ApiError> {
+    let (offset, limit) = pagination.window();
+
+    with_conn(move |conn| {
+        if get_proposals_by_ids(conn, &[id])?.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(RespProposalVotes {
+            proposal_id: id,
+            totals: get_vote_totals_by_proposal_id(conn, id)?,
+            votes: get_proposal_votes_page(conn, id, offset, limit)?,
+        }))
+    })
+    .await?
+    .map(|votes| Json(provenance.apply(votes)))
+    .ok_or_else(|| ApiError::not_found(format!("proposal {} not found", id)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/votes/{key}",
+    tag = "votes",
+    params(("key" = String, Path, description = "Vote key"), ProvenanceParam),
+    responses(
+        (status = 200, description = "The vote", body = Votes),
+        (status = 404, description = "Unknown vote", body = RespError),
+    )
+)]
+pub async fn get_vote_handler(
+    Path(key): Path<String>,
+    Query(provenance): Query<ProvenanceParam>,
+) -> Result<Json<Votes>, ApiError> {
+    let vote_key = key.clone();
+
+    with_conn(move |conn| get_vote_by_key(conn, vote_key))
+        .await?
+        .map(|vote| Json(provenance.apply(vote)))
+        .ok_or_else(|| ApiError::not_found(format!("vote {} not found", key)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/accounts/{address}/votes",
+    tag = "votes",
+    params(("address" = String, Path, description = "Aleo address"), Pagination, ProvenanceParam),
+    responses(
+        (status = 200, description = "Votes cast by the address, most recent first", body = [RespVoterVote]),
+    )
+)]
+pub async fn list_account_votes_handler(
+    Path(address): Path<String>,
+    Query(pagination): Query<Pagination>,
+    Query(provenance): Query<ProvenanceParam>,
+) -> Result<Json<Vec<RespVoterVote>>, ApiError> {
+    let (offset, limit) = pagination.window();
+
+    let (votes, proposals, daos) = with_conn(move |conn| {
+        let votes = get_vote_by_voter(conn, address, offset, limit)?;
+
+        let proposal_ids: Vec<i64> = votes.iter().map(|vote| vote.proposal_id).collect();
+        let proposals: HashMap<i64, Proposals> = get_proposals_by_ids(conn, &proposal_ids)?
+            .into_iter()
+            .map(|proposal| (proposal.id, proposal))
+            .collect();
+
+        let dao_ids: Vec<i64> = proposals.values().map(|proposal| proposal.dao_id).collect();
+        let daos: HashMap<i64, Daos> = get_daos_by_ids(conn, &dao_ids)?
+            .into_iter()
+            .map(|dao| (dao.id, dao))
+            .collect();
+
+        Ok((votes, proposals, daos))
+    })
+    .await?;
+
+    let votes = votes
+        .into_iter()
+        .map(|vote| {
+            let proposal = proposals.get(&vote.proposal_id).cloned();
+            let dao = proposal
+                .as_ref()
+                .and_then(|proposal| daos.get(&proposal.dao_id).cloned());
+            RespVoterVote {
+                vote,
+                proposal,
+                dao,
+            }
+        })
+        .collect();
+
+    Ok(Json(provenance.apply(votes)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/token-infos",
+    tag = "tokens",
+    params(
+        ("ids" = Option<String>, Query, description = "Comma separated or repeated token info ids, pagination is ignored when set"),
+        Pagination, ProvenanceParam,
+    ),
+    responses(
+        (status = 200, description = "Token infos ordered by id, unknown ids are skipped", body = [TokenInfos]),
+        (status = 400, description = "Invalid parameters", body = RespError),
+    )
+)]
+pub async fn list_token_infos_handler(
+    Query(pagination): Query<Pagination>,
+    Query(provenance): Query<ProvenanceParam>,
+    RawQuery(query): RawQuery,
+) -> Result<Json<Vec<TokenInfos>>, ApiError> {
+    let ids = parse_ids(&query, "ids")?;
+    let (offset, limit) = pagination.window();
+
+    let token_infos = with_conn(move |conn| match ids {
+        Some(ids) => {
+            let mut token_infos = get_token_infos_by_ids(conn, &ids)?;
+            token_infos.sort_by_key(|token_info| token_info.id);
+            Ok(token_infos)
+        }
+        None => get_token_infos_page(conn, offset, limit),
+    })
+    .await?;
+
+    Ok(Json(provenance.apply(token_infos)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/token-infos/{id}",
+    tag = "tokens",
+    params(("id" = i64, Path, description = "Token info id"), ProvenanceParam),
+    responses(
+        (status = 200, description = "The token info", body = TokenInfos),
+        (status = 404, description = "Unknown token info", body = RespError),
+    )
+)]
+pub async fn get_token_info_handler(
+    Path(id): Path<i64>,
+    Query(provenance): Query<ProvenanceParam>,
+) -> Result<Json<TokenInfos>, ApiError> {
+    with_conn(move |conn| get_token_infos_by_ids(conn, &[id]))
+        .await?
+        .pop()
+        .map(|row| Json(provenance.apply(row)))
+        .ok_or_else(|| ApiError::not_found(format!("token info {} not found", id)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/token-infos",
+    tag = "tokens",
+    request_body = TokenInfos,
+    responses(
+        (status = 201, description = "Token info created, an existing id is left untouched", body = TokenInfos),
+    )
+)]
+pub async fn create_token_info_handler(
+    Json(mut token_info): Json<TokenInfos>,
+) -> Result<(StatusCode, Json<TokenInfos>), ApiError> {
+    token_info.clear_provenance();
+    let new_token_info = token_info.clone();
+    with_conn(move |conn| insert_token_info(conn, new_token_info)).await?;
+
+    Ok((StatusCode::CREATED, Json(token_info)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/accounts/{address}/balances",
+    tag = "accounts",
+    params(("address" = String, Path, description = "Aleo address"), ProvenanceParam),
+    responses(
+        (status = 200, description = "Balances held by the address", body = [Balances]),
+        (status = 400, description = "Invalid address", body = RespError),
+    )
+)]
+pub async fn get_account_balances_handler(
+    Path(address): Path<String>,
+    Query(provenance): Query<ProvenanceParam>,
+) -> Result<Json<Vec<Balances>>, ApiError> {
+    let hash_addr = bhp256_hash_address(&address).map_err(ApiError::bad_request)?;
+
+    let balances =
+        with_conn(move |conn| get_balances_by_owner(conn, hash_addr.to_string())).await?;
+
+    Ok(Json(provenance.apply(balances)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/accounts/{address}/stakes",
+    tag = "accounts",
+    params(("address" = String, Path, description = "Aleo address"), ProvenanceParam),
+    responses(
+        (status = 200, description = "Stakes held by the address", body = [StakeAmounts]),
+        (status = 400, description = "Invalid address", body = RespError),
+    )
+)]
+pub async fn get_account_stakes_handler(
+    Path(address): Path<String>,
+    Query(provenance): Query<ProvenanceParam>,
+) -> Result<Json<Vec<StakeAmounts>>, ApiError> {
+    let hash_addr = bhp256_hash_address(&address).map_err(ApiError::bad_request)?;
+
+    let stakes = with_conn(move |conn| get_stakes_by_owner(conn, hash_addr.to_string())).await?;
+
+    Ok(Json(provenance.apply(stakes)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/accounts/{address}/profile",
+    tag = "accounts",
+    params(("address" = String, Path, description = "Aleo address"), ProvenanceParam),
+    responses(
+        (status = 200, description = "Profile of the address", body = Profiles),
+        (status = 404, description = "The address has no profile", body = RespError),
+    )
+)]
+pub async fn get_account_profile_handler(
+    Path(address): Path<String>,
+    Query(provenance): Query<ProvenanceParam>,
+) -> Result<Json<Profiles>, ApiError> {
+    let addresses = vec![address.clone()];
+
+    with_conn(move |conn| get_profiles_by_addresses(conn, &addresses))
+        .await?
+        .pop()
+        .map(|row| Json(provenance.apply(row)))
+        .ok_or_else(|| ApiError::not_found(format!("profile {} not found", address)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/accounts/{address}/profile",
+    tag = "accounts",
+    params(("address" = String, Path, description = "Aleo address")),
+    request_body = ReqProfile,
+    responses(
+        (status = 200, description = "Profile created or replaced", body = Profiles),
+    )
+)]
+pub async fn put_account_profile_handler(
+    Path(address): Path<String>,
+    Json(profile): Json<ReqProfile>,
+) -> Result<Json<Profiles>, ApiError> {
+    let profile = Profiles {
+        address,
+        name: profile.name,
+        avatar: profile.avatar,
+        bio: profile.bio,
+        updated_at_height: None,
+        updated_by_transition_id: None,
+        updated_at_timestamp: None,
+    };
+    let new_profile = profile.clone();
+    with_conn(move |conn| upsert_profile(conn, new_profile)).await?;
+
+    Ok(Json(profile))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/view-keys",
+    tag = "accounts",
+    request_body = ReqViewKey,
+    responses(
+        (status = 201, description = "View key registered, the records it owns are indexed from the next block on", body = RespViewKey),
+        (status = 400, description = "Invalid view key", body = RespError),
+        (status = 503, description = "Registration is disabled, VIEW_KEY_ENCRYPTION_KEY is not set", body = RespError),
+    )
+)]
+pub async fn register_view_key_handler(
+    Json(request): Json<ReqViewKey>,
+) -> Result<(StatusCode, Json<RespViewKey>), ApiError> {
+    if !view_keys::enabled() {
+        return Err(ApiError::unavailable("view key registration is disabled"));
+    }
+    let view_key = ViewKey::<Testnet3>::from_str(request.view_key.trim())
+        .map_err(|_| ApiError::bad_request("invalid view key"))?;
+
+    let address = view_key.to_address().to_string();
+    let access_token = view_keys::new_access_token();
+    let registered = ViewKeys {
+        address: address.clone(),
+        encrypted_view_key: view_keys::encrypt(&view_key)?,
+        access_token_hash: view_keys::hash_access_token(&access_token),
+        registered_at: Utc::now().timestamp(),
+        backfilled_at_height: None,
+    };
+    with_conn(move |conn| upsert_view_key(conn, &registered)).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(RespViewKey {
+            address,
+            access_token,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/accounts/{address}/token-records",
+    tag = "accounts",
+    params(("address" = String, Path, description = "Aleo address with a registered view key"), SpentParam),
+    security(("access_token" = [])),
+    responses(
+        (status = 200, description = "Token records owned by the address, oldest first", body = [Token]),
+        (status = 401, description = "Missing or invalid access token", body = RespError),
+    )
+)]
+pub async fn list_account_token_records_handler(
+    Path(address): Path<String>,
+    Query(spent): Query<SpentParam>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Token>>, ApiError> {
+    authenticate(&headers, &address).await?;
+
+    let include_spent = spent.include_spent.unwrap_or(false);
+    let tokens = with_conn(move |conn| get_tokens_by_owner(conn, &address, include_spent)).await?;
+
+    Ok(Json(tokens))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/accounts/{address}/token-holdings",
+    tag = "accounts",
+    params(("address" = String, Path, description = "Aleo address with a registered view key")),
+    security(("access_token" = [])),
+    responses(
+        (status = 200, description = "Unspent token records of the address summed by token info id", body = [RespTokenHolding]),
+        (status = 401, description = "Missing or invalid access token", body = RespError),
+    )
+)]
+pub async fn list_account_token_holdings_handler(
+    Path(address): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<RespTokenHolding>>, ApiError> {
+    authenticate(&headers, &address).await?;
+
+    let tokens = with_conn(move |conn| get_tokens_by_owner(conn, &address, false)).await?;
+
+    let mut holdings: BTreeMap<i64, RespTokenHolding> = BTreeMap::new();
+    for token in tokens {
+        let holding = holdings
+            .entry(token.token_info_id)
+            .or_insert_with(|| RespTokenHolding {
+                token_info_id: token.token_info_id,
+                amount: 0u64.into(),
+                records: 0,
+            });
+        holding.amount.0 += token.amount.0;
+        holding.records += 1;
+    }
+
+    Ok(Json(holdings.into_values().collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/records",
+    tag = "records",
+    params(BlockRange, RecordFilter, Pagination),
+    responses(
+        (status = 200, description = "Records indexed in the block range ordered by height", body = [RespRecords]),
+    )
+)]
+pub async fn list_records_handler(
+    Query(range): Query<BlockRange>,
+    Query(filter): Query<RecordFilter>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Vec<RespRecords>>, ApiError> {
+    let (offset, limit) = pagination.window();
+    let start_block = range.start_block.unwrap_or(0);
+    let end_block = range.end_block.unwrap_or(i64::MAX);
+
+    let records = with_conn(move |conn| {
+        get_records_page(conn, start_block, end_block, &filter, offset, limit)
+    })
+    .await?;
+    let records = records
+        .iter()
+        .map(resp_record)
+        .collect::<Result<Vec<RespRecords>, _>>()?;
+
+    Ok(Json(records))
+}
+
+fn block_with_transactions(
+    conn: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    block: Option<Blocks>,
+) -> Result<Option<RespBlock>, anyhow::Error> {
+    let block = match block {
+        Some(block) => block,
+        None => return Ok(None),
+    };
+    let transactions = get_transactions_by_height(conn, block.height)?;
+
+    Ok(Some(RespBlock {
+        block,
+        transactions,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/blocks/latest",
+    tag = "blocks",
+    responses(
+        (status = 200, description = "The highest indexed block and its transactions", body = RespBlock),
+        (status = 404, description = "No block indexed yet", body = RespError),
+    )
+)]
+pub async fn get_latest_block_handler() -> Result<Json<RespBlock>, ApiError> {
+    with_conn(move |conn| {
+        let block = get_latest_block(conn)?;
+        block_with_transactions(conn, block)
+    })
+    .await?
+    .map(Json)
+    .ok_or_else(|| ApiError::not_found("no block indexed yet"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/blocks/{height}",
+    tag = "blocks",
+    params(("height" = i64, Path, description = "Block height")),
+    responses(
+        (status = 200, description = "The block and its transactions", body = RespBlock),
+        (status = 404, description = "Block not indexed", body = RespError),
+    )
+)]
+pub async fn get_block_handler(Path(height): Path<i64>) -> Result<Json<RespBlock>, ApiError> {
+    with_conn(move |conn| {
+        let block = get_block_by_height(conn, height)?;
+        block_with_transactions(conn, block)
+    })
+    .await?
+    .map(Json)
+    .ok_or_else(|| ApiError::not_found(format!("block {} not found", height)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/stats",
+    tag = "stats",
+    responses(
+        (status = 200, description = "Staking and funds statistics, overall and broken down per token info and per DAO", body = RespStats),
+    )
+)]
+pub async fn get_stats_handler() -> Result<Json<RespStats>, ApiError> {
+    let stats = with_conn(|conn| {
+        Ok(RespStats {
+            pledgers_total: get_pledgers_summary(conn)?,
+            stake_funds_total: get_stake_funds_summary(conn)?,
+            funds_total: get_funds_summary(conn)?,
+        })
+    })
+    .await?;
+
+    Ok(Json(stats))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/stats/pledgers",
+    tag = "stats",
+    params(
+        ("token_info_ids" = String, Query, description = "Comma separated or repeated token info ids"),
+    ),
+    responses(
+        (status = 200, description = "Number of distinct stakers of each token", body = [RespTokenPledgers]),
+        (status = 400, description = "Invalid parameters", body = RespError),
+    )
+)]
+pub async fn list_pledgers_handler(
+    RawQuery(query): RawQuery,
+) -> Result<Json<Vec<RespTokenPledgers>>, ApiError> {
+    let ids = parse_ids(&query, "token_info_ids")?.unwrap_or_default();
+
+    let pledgers = with_conn(move |conn| {
+        let mut pledgers = Vec::with_capacity(ids.len());
+        for token_info_id in ids {
+            pledgers.push(RespTokenPledgers {
+                token_info_id,
+                pledgers: get_pledgers_by_token_info_id(conn, token_info_id)?,
+            });
+        }
+        Ok(pledgers)
+    })
+    .await?;
+
+    Ok(Json(pledgers))
+}
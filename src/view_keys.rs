@@ -0,0 +1,99 @@
+use crate::{models::ViewKeys, storage::Storage};
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, Context, Error};
+use sha2::{Digest, Sha256};
+use snarkvm::prelude::{Field, GraphKey, Testnet3, ViewKey};
+use std::{env, str::FromStr};
+
+/// Environment variable holding the 32-byte key, in hex, that encrypts the
+/// registered view keys at rest. Both `serve` and `sync` need it once a view
+/// key is registered.
+pub const ENCRYPTION_KEY_VAR: &str = "VIEW_KEY_ENCRYPTION_KEY";
+
+/// Length of the AES-GCM nonce prepended to an encrypted view key.
+const NONCE_LEN: usize = 12;
+
+fn cipher() -> Result<Aes256Gcm, Error> {
+    let key = env::var(ENCRYPTION_KEY_VAR)
+        .map_err(|_| anyhow!("{} must be set to use view keys", ENCRYPTION_KEY_VAR))?;
+    let key =
+        hex::decode(key.trim()).with_context(|| format!("{} is not hex", ENCRYPTION_KEY_VAR))?;
+    Aes256Gcm::new_from_slice(&key).map_err(|_| anyhow!("{} must be 32 bytes", ENCRYPTION_KEY_VAR))
+}
+
+/// Whether view keys can be registered, `VIEW_KEY_ENCRYPTION_KEY` being a valid key.
+pub fn enabled() -> bool {
+    cipher().is_ok()
+}
+
+/// The view key encrypted with a random nonce, the nonce first.
+pub fn encrypt(view_key: &ViewKey<Testnet3>) -> Result<Vec<u8>, Error> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher()?
+        .encrypt(&nonce, view_key.to_string().as_bytes())
+        .map_err(|_| anyhow!("View key encryption failed"))?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+pub fn decrypt(encrypted_view_key: &[u8]) -> Result<ViewKey<Testnet3>, Error> {
+    if encrypted_view_key.len() < NONCE_LEN {
+        return Err(anyhow!("Encrypted view key is too short"));
+    }
+    let (nonce, ciphertext) = encrypted_view_key.split_at(NONCE_LEN);
+    let plaintext = cipher()?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            anyhow!(
+                "View key decryption failed, {} may have changed",
+                ENCRYPTION_KEY_VAR
+            )
+        })?;
+    ViewKey::from_str(std::str::from_utf8(&plaintext)?)
+}
+
+/// A random access token, 32 bytes in hex.
+pub fn new_access_token() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// What is stored of an access token, its SHA-256 in hex.
+pub fn hash_access_token(access_token: &str) -> String {
+    hex::encode(Sha256::digest(access_token.as_bytes()))
+}
+
+/// A registered view key, decrypted.
+pub struct RegisteredViewKey {
+    pub address: String,
+    pub view_key: ViewKey<Testnet3>,
+    /// Key of the tags of the records owned by the view key, see `Record::tag`.
+    pub sk_tag: Field<Testnet3>,
+    /// `None` until the records synced before the registration are indexed.
+    pub backfilled_at_height: Option<i64>,
+}
+
+impl TryFrom<&ViewKeys> for RegisteredViewKey {
+    type Error = Error;
+
+    fn try_from(view_key: &ViewKeys) -> Result<Self, Self::Error> {
+        let decrypted = decrypt(&view_key.encrypted_view_key)
+            .with_context(|| format!("view key of {}", view_key.address))?;
+        Ok(RegisteredViewKey {
+            address: view_key.address.clone(),
+            sk_tag: GraphKey::try_from(&decrypted)?.sk_tag(),
+            view_key: decrypted,
+            backfilled_at_height: view_key.backfilled_at_height,
+        })
+    }
+}
+
+/// The registered view keys, decrypted. `VIEW_KEY_ENCRYPTION_KEY` is only
+/// needed when there are some.
+pub fn registered(conn: &mut dyn Storage) -> Result<Vec<RegisteredViewKey>, Error> {
+    conn.get_view_keys()?
+        .iter()
+        .map(RegisteredViewKey::try_from)
+        .collect()
+}